@@ -1,53 +1,189 @@
-use crate::currency::WalletError;
-use crate::game::*;
-use crate::ticket::*;
-use crate::upgrade::*;
+use ticket_tycoon::catalog;
+use ticket_tycoon::clock::{Clock, SystemClock};
+use ticket_tycoon::command::*;
+use ticket_tycoon::currency::{Amount, WalletError};
+use ticket_tycoon::game::*;
+use ticket_tycoon::promotion::*;
+use ticket_tycoon::save;
+use ticket_tycoon::ticket::*;
+use ticket_tycoon::upgrade::*;
 
-use dioxus::{logger::tracing::info, prelude::*};
-use std::time::Duration;
+use dioxus::{
+    logger::tracing::{info, warn},
+    prelude::*,
+};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
 use tokio::time::sleep;
 
 pub fn app() -> Element {
-    let mut state = use_signal(GameState::new);
+    let mut slot = use_signal(|| None::<String>);
+    let slots = use_hook(|| match save::Slots::open() {
+        Ok(slots) => slots,
+        Err(err) => {
+            warn!("Couldn't open save slots, using ./saves: {:?}", err);
+            save::Slots::new(PathBuf::from("saves"))
+        }
+    });
+    use_hook(|| {
+        if let (_, Some(errors)) = catalog::loaded() {
+            warn!(
+                "Using the built in upgrades, the catalog file has problems: {:?}",
+                errors
+            );
+        }
+    });
+
+    match slot() {
+        None => rsx! {
+            SlotPicker {
+                slots,
+                on_play: move |name| slot.set(Some(name)),
+            }
+        },
+        Some(name) => rsx! {
+            Game {
+                key: "{name}",
+                slots,
+                slot: name,
+                on_exit: move |_| slot.set(None),
+            }
+        },
+    }
+}
+
+/// The main game screen for one save slot
+#[component]
+fn Game(slots: save::Slots, slot: String, on_exit: EventHandler<()>) -> Element {
+    let mut away = use_signal(|| None::<Offline>);
+    // a slot that doesn't load is never played or saved over, so the player
+    // can still get it back
+    let mut broken = use_signal(|| None::<String>);
+    let mut state = use_signal(|| match slots.load(&slot) {
+        Ok(mut game) => {
+            away.set(game.catch_up(&SystemClock, GameState::MAX_OFFLINE_SECS));
+            game
+        }
+        Err(err) => {
+            warn!("Couldn't load {}: {:?}", slot, err);
+            broken.set(Some(save_error(err)));
+            GameState::new()
+        }
+    });
     let mut error = use_signal(String::new);
     let mut show = use_signal(|| false);
+    let mut transfer = use_signal(String::new);
 
-    if state.read().working().is_empty() {
-        state.write().init_queue();
-    }
+    let save_now = use_callback(move |_: ()| {
+        if broken.peek().is_some() {
+            return;
+        }
+        if let Err(err) = slots.save(&slot, &state.read()) {
+            warn!("Couldn't save game: {:?}", err);
+        }
+    });
 
     use_future(move || async move {
+        if broken.peek().is_some() {
+            return;
+        }
+        let mut autosave = save::Autosave::new(Instant::now());
+        let mut watcher = catalog::watched_path().map(catalog::Watcher::new);
         loop {
-            state.write().autosolve();
+            state.write().tick(&SystemClock);
+
+            // pick up edits to the upgrade catalog without restarting
+            if let Some(watcher) = watcher.as_mut() {
+                match watcher.poll() {
+                    Some(Ok(upgrades)) => {
+                        info!("Reloaded upgrades from {}", watcher.path().display());
+                        catalog::replace(upgrades.clone());
+                        state.write().reload_upgrades(upgrades);
+                    }
+                    Some(Err(errors)) => {
+                        warn!("Not reloading {}: {:?}", watcher.path().display(), errors);
+                        error.set(format!(
+                            "Upgrade catalog has {} problem(s), see the log",
+                            errors.len()
+                        ));
+                    }
+                    None => {}
+                }
+            }
+
+            let now = Instant::now();
+            if state.write().take_save_request() {
+                autosave.request(now);
+            }
+            if autosave.due(now) {
+                save_now.call(());
+                autosave.saved(now);
+            }
+
             sleep(Duration::from_secs(1)).await;
         }
     });
 
+    if let Some(err) = broken() {
+        return rsx! {
+            div {
+                style: "padding: 0px 30px; display: flex; flex-direction: column; gap: 10px;",
+
+                Error { err }
+                button {
+                    onclick: move |_| on_exit.call(()),
+                    "Back to Save Slots"
+                }
+            }
+        };
+    }
+
     rsx! {
         Header {
             cash: state.read().wallet().cash(),
             xp: state.read().wallet().xp(),
+            reputation: state.read().reputation(),
             on_input:  move |_| show.set(!show()),
+            on_exit: move |_| {
+                save_now.call(());
+                on_exit.call(());
+            },
+        }
+        if let Some(offline) = away() {
+            Away { offline, on_close: move |_| away.set(None) }
         }
         div {
             style: "display: flex; flex-direction: row; justify-content: space-around; padding: 15px 5px; min-height: 385px",
 
             Queue {
                 queue: state.read().working().to_vec(),
+                backlog: state.read().backlog(),
+                now: SystemClock.now(),
                 on_click: move |i| {
                     error.set(String::new());
-                    state.write().click_ticket(i)
+                    // the ticket may have been closed since it was drawn
+                    let _ = state.write().apply(Command::ClickTicket(i));
                 }
             }
             Upgrades {
-                upgrades: state.read().avail_upgrades(),
+                upgrades: {
+                    let game = state.read();
+                    game.avail_upgrades()
+                        .into_iter()
+                        .filter_map(|x| {
+                            let level = game.level(&x.id);
+                            game.preview(&x.id).map(|preview| (x, level, preview))
+                        })
+                        .collect::<Vec<_>>()
+                },
                 on_click: move |id| {
                     error.set(String::new());
-                    match state.write().buy_upgrade(&id) {
-                        Ok(()) => {},
-                        Err(BuyError::UpgradeUnavailable) => panic!("Should check for availability before showing to user"),
-                        Err(BuyError::Wallet(WalletError::InsufficientCash)) => error.set("Not enough cash to buy this upgrade".to_string()),
-                        Err(BuyError::Wallet(WalletError::InsufficientXP)) => error.set("Not enough XP to buy this upgrade".to_string()),
+                    let bought = state.write().apply(Command::BuyUpgrade(id));
+                    match bought {
+                        Ok(()) | Err(CommandError::NoTicket(_)) | Err(CommandError::Promotion(_)) => {},
+                        // reputation, hot reloads and max levels can all take it away after it was drawn
+                        Err(CommandError::Buy(BuyError::UpgradeUnavailable)) => error.set("That upgrade isn't available any more".to_string()),
+                        Err(CommandError::Buy(BuyError::Wallet(WalletError::Insufficient { kind, short }))) => error.set(format!("Need {} more to buy this upgrade", kind.show(short))),
                     }
                 }
             }
@@ -56,26 +192,256 @@ pub fn app() -> Element {
         div {
             style: "border-bottom: 1px solid black;",
         }
+        Promotion {
+            seniority: state.read().career().seniority(),
+            promotions: state.read().career().promotions(),
+            pending: state.read().career().pending(),
+            perks: state.read().career().avail_perks(),
+            on_promote: move |_| {
+                error.set(String::new());
+                match state.write().apply(Command::Promote) {
+                    Ok(()) => save_now.call(()),
+                    Err(_) => error.set("Earn more cash before asking for a promotion".to_string()),
+                }
+            },
+            on_buy: move |id| {
+                error.set(String::new());
+                let bought = state.write().apply(Command::BuyPerk(id));
+                if let Err(CommandError::Promotion(PromotionError::NotEnoughSeniority)) = bought {
+                    error.set("Not enough seniority for this perk".to_string());
+                }
+            },
+        }
         if show() {
             Stat { stats: state.read().stats() }
         }
+        Transfer {
+            text: transfer(),
+            on_input: move |text| transfer.set(text),
+            on_export: move |_| {
+                error.set(String::new());
+                match save::export(&state.read()) {
+                    Ok(text) => transfer.set(text),
+                    Err(err) => error.set(save_error(err)),
+                }
+            },
+            on_import: move |_| {
+                error.set(String::new());
+                match save::import(&transfer()) {
+                    Ok(game) => {
+                        state.set(game);
+                        transfer.set(String::new());
+                        save_now.call(());
+                    }
+                    Err(err) => error.set(save_error(err)),
+                }
+            },
+        }
     }
 }
 
 #[component]
-fn Header(cash: u64, xp: u64, on_input: EventHandler<()>) -> Element {
+fn Header(
+    cash: Amount,
+    xp: Amount,
+    reputation: Amount,
+    on_input: EventHandler<()>,
+    on_exit: EventHandler<()>,
+) -> Element {
     rsx! {
         div {
             style: "padding: 0px 30px 15px 30px; display: flex; flex-direction: row; justify-content: space-between; border-bottom: 1px solid black;",
 
             span {
+                    button {
+                        onclick: move |_| on_exit.call(()),
+                        "Save Slots"
+                    }
                     label { "show stats" }
                     input {
                         r#type: "checkbox",
                         oninput: move |_| on_input.call(()),
                     }
                 }
-            span { "[ ${cash} ]  [ {xp} XP ]" }
+            span { "[ ${cash.short()} ]  [ {xp.short()} XP ]  [ {reputation} / {GameState::MAX_REPUTATION} reputation ]" }
+        }
+    }
+}
+
+/// Start screen for picking, creating and managing save slots
+#[component]
+fn SlotPicker(slots: save::Slots, on_play: EventHandler<String>) -> Element {
+    let slots = use_signal(|| slots);
+    let mut list = use_signal(|| slots.read().list().unwrap_or_default());
+    let mut name = use_signal(String::new);
+    let mut error = use_signal(String::new);
+
+    // refresh the list after any change to the slots
+    let finish = use_callback(move |result: Result<(), save::SaveError>| match result {
+        Ok(()) => {
+            error.set(String::new());
+            name.set(String::new());
+            list.set(slots.read().list().unwrap_or_default());
+        }
+        Err(err) => error.set(save_error(err)),
+    });
+
+    rsx! {
+        div {
+            style: "padding: 0px 30px; display: flex; flex-direction: column; gap: 10px;",
+
+            h3 { "Save Slots" }
+            div {
+                style: "display: flex; flex-direction: row; gap: 10px;",
+
+                input {
+                    r#type: "text",
+                    placeholder: "slot name",
+                    value: "{name}",
+                    oninput: move |e| name.set(e.value()),
+                }
+                button {
+                    onclick: move |_| {
+                        let created = slots.read().create(&name()).map(|_| ());
+                        finish.call(created);
+                    },
+                    "New"
+                }
+            }
+            Error { err: error.read() }
+
+            for info in list() {
+                {
+                    let play_id = info.name.clone();
+                    let rename_id = info.name.clone();
+                    let dup_id = info.name.clone();
+                    let delete_id = info.name.clone();
+                    let played = info
+                        .last_played
+                        .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+                        .unwrap_or("never".to_string());
+                    rsx! {
+                        div {
+                            style: "border: 1px solid black; padding: 5px; display: flex; flex-direction: row; justify-content: space-between;",
+                            key: "{info.name}",
+
+                            div {
+                                h4 { "{info.name}" }
+                                if info.broken {
+                                    span { "[ can't be loaded ]  last played {played}" }
+                                } else {
+                                    span { "[ ${info.cash.short()} ]  [ {info.xp.short()} XP ]  [ {info.purchased} upgrades ]  last played {played}" }
+                                }
+                            }
+                            div {
+                                style: "display: flex; flex-direction: row; gap: 5px; align-items: center;",
+
+                                button {
+                                    // stay here and say why if the slot won't load
+                                    onclick: move |_| match slots.read().load(&play_id) {
+                                        Ok(_) => on_play.call(play_id.clone()),
+                                        Err(err) => error.set(save_error(err)),
+                                    },
+                                    "Play"
+                                }
+                                button {
+                                    onclick: move |_| finish.call(slots.read().rename(&rename_id, &name())),
+                                    "Rename"
+                                }
+                                button {
+                                    onclick: move |_| finish.call(slots.read().duplicate(&dup_id, &name())),
+                                    "Duplicate"
+                                }
+                                button {
+                                    onclick: move |_| finish.call(slots.read().delete(&delete_id)),
+                                    "Delete"
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Message to show the player when something goes wrong with saving/loading
+fn save_error(err: save::SaveError) -> String {
+    match err {
+        save::SaveError::BadSlotName(name) if name.is_empty() => {
+            "Type a slot name first".to_string()
+        }
+        save::SaveError::BadSlotName(name) => format!(
+            "\"{}\" can only use letters, numbers, spaces, - and _",
+            name
+        ),
+        save::SaveError::SlotExists(name) => format!("There's already a slot called \"{}\"", name),
+        save::SaveError::NoSlot(name) => format!("There's no slot called \"{}\"", name),
+        save::SaveError::BadExport | save::SaveError::BadChecksum => {
+            "That isn't a complete exported save".to_string()
+        }
+        save::SaveError::Json(_) => "That save is damaged and can't be loaded".to_string(),
+        save::SaveError::UnknownVersion(_) => {
+            "That save is from a newer version of the game".to_string()
+        }
+        save::SaveError::UnknownUpgrades(ids) => {
+            format!(
+                "That save has upgrades that don't exist: {}",
+                ids.join(", ")
+            )
+        }
+        err => format!("Something went wrong: {:?}", err),
+    }
+}
+
+/// Export the run as text, or paste one in to replace it
+#[component]
+fn Transfer(
+    text: String,
+    on_input: EventHandler<String>,
+    on_export: EventHandler<()>,
+    on_import: EventHandler<()>,
+) -> Element {
+    rsx! {
+        div {
+            style: "padding: 15px 30px; display: flex; flex-direction: row; gap: 10px; align-items: center;",
+
+            textarea {
+                style: "flex-grow: 1; font-family: monospace; font-size: 11px;",
+                rows: 2,
+                placeholder: "exported save",
+                value: "{text}",
+                oninput: move |e| on_input.call(e.value()),
+            }
+            button {
+                onclick: move |_| on_export.call(()),
+                "Export"
+            }
+            button {
+                onclick: move |_| on_import.call(()),
+                "Import"
+            }
+        }
+    }
+}
+
+/// Summary of the progress made while the game was closed
+#[component]
+fn Away(offline: Offline, on_close: EventHandler<()>) -> Element {
+    let hours = offline.secs / 3600;
+    let mins = (offline.secs % 3600) / 60;
+
+    rsx! {
+        div {
+            style: "margin: 15px 30px 0px 30px; border: 1px solid black; padding: 5px; display: flex; flex-direction: row; justify-content: space-between; align-items: center;",
+
+            span {
+                "While you were away ({hours}h {mins}m): closed {offline.tickets} tickets, earned ${offline.cash.short()} and {offline.xp.short()} XP"
+            }
+            button {
+                onclick: move |_| on_close.call(()),
+                "OK"
+            }
         }
     }
 }
@@ -92,14 +458,23 @@ fn Error(err: String) -> Element {
 }
 
 #[component]
-fn Queue(queue: Vec<Ticket>, on_click: EventHandler<usize>) -> Element {
+fn Queue(queue: Vec<Ticket>, backlog: usize, now: i64, on_click: EventHandler<usize>) -> Element {
+    // most urgent first, keeping each ticket's index in the working set for clicks
+    let mut sorted: Vec<(usize, &Ticket)> = queue.iter().enumerate().collect();
+    sorted.sort_by_key(|(_, ticket)| *ticket.priority());
+
     rsx! {
         div {
             style: "width: 375px; display: flex; flex-direction: column; gap: 10px;",
 
-            h3 { "Ticket Queue" }
+            div {
+                style: "display: flex; justify-content: space-between; align-items: baseline;",
 
-            for (i, ticket) in queue.iter().enumerate() {
+                h3 { "Ticket Queue" }
+                span { "{backlog} waiting" }
+            }
+
+            for (i, ticket) in sorted {
                 {
                     let pct = (ticket.clicked() as f32 / ticket.goal() as f32 * 100.0).min(100.0);
 
@@ -112,12 +487,13 @@ fn Queue(queue: Vec<Ticket>, on_click: EventHandler<usize>) -> Element {
                                 style: "display: flex; justify-content: space-between;",
 
                                 span { "{ticket.name()}" }
-                                span { {format!("{:?} - {:?}", ticket.category(), ticket.difficulty()) }}
+                                span { {format!("{:?} {:?} - {:?}", ticket.priority(), ticket.category(), ticket.difficulty()) }}
                             }
                             div {
                                 style: "display: flex; justify-content: space-between;",
 
                                 ProgBar { pct }
+                                Sla { deadline: ticket.deadline(), now }
                                 {
                                     let text = if pct == 100.0 { "Close" } else { "Work" };
                                     rsx! {
@@ -157,33 +533,74 @@ fn ProgBar(pct: f32) -> Element {
     }
 }
 
+/// Time left until a ticket breaches its SLA
 #[component]
-fn Upgrades(upgrades: Vec<Upgrade>, on_click: EventHandler<String>) -> Element {
+fn Sla(deadline: Option<i64>, now: i64) -> Element {
+    let (text, color) = match deadline {
+        None => ("--:--".to_string(), "black"),
+        Some(deadline) if now > deadline => ("BREACHED".to_string(), "red"),
+        Some(deadline) => {
+            let left = deadline - now;
+            (format!("{}:{:02}", left / 60, left % 60), "black")
+        }
+    };
+
+    rsx! {
+        div {
+            style: "font-family: monospace; color: {color};",
+            "{text}"
+        }
+    }
+}
+
+#[component]
+fn Upgrades(upgrades: Vec<(Upgrade, u32, Preview)>, on_click: EventHandler<String>) -> Element {
+    // the upgrade the mouse is over, which shows what buying it would change
+    let mut hovered = use_signal(|| None::<String>);
+
     rsx! {
         div {
             style: "width: 310px; display: flex; flex-direction: column; gap: 10px",
 
             h3 { "Upgrades" }
-            for (i, upgrade) in upgrades.iter().enumerate() {
+            for (i, (upgrade, level, preview)) in upgrades.iter().enumerate() {
                 {
                     let id = upgrade.id.clone();
+                    let hover_id = upgrade.id.clone();
+                    let level = *level;
+                    let name = match (&upgrade.repeat, level) {
+                        (Some(_), 1..) => format!("{} (level {})", upgrade.name, level),
+                        _ => upgrade.name.clone(),
+                    };
+                    let changes = if hovered().as_ref() == Some(&upgrade.id) {
+                        changes(preview)
+                    } else {
+                        Vec::new()
+                    };
                     rsx! {
                         div {
                             style: "border: 1px solid black; padding: 5px;",
                             key: "{i}",
+                            onmouseenter: move |_| hovered.set(Some(hover_id.clone())),
+                            onmouseleave: move |_| hovered.set(None),
 
-                            h4 { "{upgrade.name}" }
+                            h4 { "{name}" }
                             p  { "{upgrade.desc}" }
+                            if !changes.is_empty() {
+                                ul {
+                                    style: "margin: 0 0 5px; padding-left: 20px; font-size: 13px",
+
+                                    for change in changes {
+                                        li { "{change}" }
+                                    }
+                                }
+                            }
                             div {
                                 style: "display: flex; flex-direction: row; justify-content: space-between",
 
                                 {
-                                    let cost = match (upgrade.cost.cash(), upgrade.cost.xp()) {
-                                        (0, 0) => "".to_string(),
-                                        (x, 0) => format!("${}", x),
-                                        (0, x) => format!("{} XP", x),
-                                        (x, y) => format!("${} and {} XP", x, y),
-                                    };
+                                    let price = upgrade.cost_at(level);
+                                    let cost = price.short();
                                     rsx! {
                                         span { "{cost}" }
                                         button {
@@ -201,6 +618,98 @@ fn Upgrades(upgrades: Vec<Upgrade>, on_click: EventHandler<String>) -> Element {
     }
 }
 
+/// Getting promoted, and the perks that seniority from promotions can buy
+#[component]
+fn Promotion(
+    seniority: u64,
+    promotions: u32,
+    pending: u64,
+    perks: Vec<Perk>,
+    on_promote: EventHandler<()>,
+    on_buy: EventHandler<String>,
+) -> Element {
+    rsx! {
+        div {
+            style: "padding: 10px 30px; display: flex; flex-direction: column; gap: 10px; border-bottom: 1px solid black;",
+
+            div {
+                style: "display: flex; flex-direction: row; justify-content: space-between; align-items: center",
+
+                span { "[ {seniority} seniority ]  [ promoted {promotions} times ]" }
+                button {
+                    disabled: pending == 0,
+                    onclick: move |_| on_promote.call(()),
+                    "Get promoted: +{pending} seniority, but start over"
+                }
+            }
+            div {
+                style: "display: flex; flex-direction: row; flex-wrap: wrap; gap: 10px",
+
+                for perk in perks {
+                    {
+                        let id = perk.id.clone();
+                        rsx! {
+                            div {
+                                style: "border: 1px solid black; padding: 5px; width: 220px",
+                                key: "{perk.id}",
+
+                                h4 { "{perk.name}" }
+                                p  { "{perk.desc}" }
+                                div {
+                                    style: "display: flex; flex-direction: row; justify-content: space-between",
+
+                                    span { "{perk.cost} seniority" }
+                                    button {
+                                        onclick: move |_| on_buy.call(id.clone()),
+                                        "Buy"
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Lines describing how the stats change in the preview, for the upgrade tooltip
+fn changes(preview: &Preview) -> Vec<String> {
+    let (before, after) = (&preview.before, &preview.after);
+    let mut changes = Vec::new();
+    let mults = [
+        ("Click multiplier", before.multiplier, after.multiplier),
+        ("Cash multiplier", before.cash_mult, after.cash_mult),
+        ("XP multiplier", before.xp_mult, after.xp_mult),
+    ];
+    for (name, a, b) in mults {
+        if a != b {
+            changes.push(format!("{} {:.2} → {:.2}", name, a, b));
+        }
+    }
+    for (diff, cat) in preview.new_autosolve() {
+        changes.push(format!("Autosolves {:?} {:?} tickets", diff, cat));
+    }
+    if after.autoclose && !before.autoclose {
+        changes.push("Closes solved tickets automatically".to_string());
+    }
+    let mut priorities: Vec<_> = after.priority_mult.iter().collect();
+    priorities.sort_by_key(|(priority, _)| **priority);
+    for (priority, b) in priorities {
+        let a = before.priority_mult.get(priority).unwrap_or(&1.0);
+        if a != b {
+            changes.push(format!("{:?} payout {:.2} → {:.2}", priority, a, b));
+        }
+    }
+    let rate = GameState::TYPICAL_CLICK_RATE;
+    changes.push(format!(
+        "Cash per minute ~${:.0} → ~${:.0}",
+        before.cash_per_min(rate, preview.arrival_rate),
+        after.cash_per_min(rate, preview.arrival_rate)
+    ));
+    changes
+}
+
 #[component]
 fn Stat(stats: Stats) -> Element {
     info!("Stats: {:?}", stats);
@@ -211,6 +720,13 @@ fn Stat(stats: Stats) -> Element {
         .map(|(diff, cat)| format!("{:?}:{:?}", diff, cat))
         .collect::<Vec<_>>()
         .join(", ");
+    let mut priority_mult = stats.priority_mult.iter().collect::<Vec<_>>();
+    priority_mult.sort_by_key(|(priority, _)| **priority);
+    let priority_mult = priority_mult
+        .iter()
+        .map(|(priority, x)| format!("{:?}:{:.2}", priority, x))
+        .collect::<Vec<_>>()
+        .join(", ");
     let timestamp = chrono::Utc::now().format("%H:%M:%SZ");
 
     rsx! {
@@ -220,7 +736,9 @@ fn Stat(stats: Stats) -> Element {
             "[{timestamp} DEBUG stats] click_mult={stats.multiplier:.2}\n",
             "[{timestamp} DEBUG stats] cash_mult={stats.cash_mult:.2}\n",
             "[{timestamp} DEBUG stats] xp_mult={stats.xp_mult:.2}\n",
-            "[{timestamp} DEBUG stats] autosolve=[{autosolve}]"
+            "[{timestamp} DEBUG stats] autosolve=[{autosolve}]\n",
+            "[{timestamp} DEBUG stats] autoclose={stats.autoclose}\n",
+            "[{timestamp} DEBUG stats] priority_mult=[{priority_mult}]"
         }
     }
 }
@@ -0,0 +1,348 @@
+//! Plays the game without the GUI to see how balanced it is
+//!
+//! Usage: `cargo run --bin simulate -- [options]`
+//!
+//! - `--minutes N`: how long each run plays for (default 60)
+//! - `--seeds N`: how many runs to do, with seeds 0 to N - 1 (default 10)
+//! - `--strategy random|greedy|roi`: which AI player plays (default greedy)
+//! - `--clicks N`: how many times the player clicks each second (default 3)
+//! - `--rate N`: how many new tickets come in each second on average
+//!   (default 0.2)
+//! - `--report summary|upgrades|curve`: which CSV to print (default summary)
+//! - `--upgrades FILE`: play with a different upgrade catalog
+
+use std::env;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use ticket_tycoon::catalog;
+use ticket_tycoon::command::Command;
+use ticket_tycoon::currency::Amount;
+use ticket_tycoon::game::GameState;
+use ticket_tycoon::strategy::{self, Strategy};
+
+#[cfg(test)]
+fn args(list: &[&str]) -> Result<Options, String> {
+    parse_args(list.iter().map(|x| x.to_string()))
+}
+
+#[test]
+fn default_args() {
+    assert_eq!(args(&[]), Ok(Options::default()));
+}
+
+#[test]
+fn all_args() {
+    let options = args(&[
+        "--minutes",
+        "5",
+        "--seeds",
+        "2",
+        "--strategy",
+        "roi",
+        "--clicks",
+        "0",
+        "--rate",
+        "0.5",
+        "--upgrades",
+        "my_upgrades.json",
+        "--report",
+        "curve",
+    ])
+    .unwrap();
+    assert_eq!(
+        options,
+        Options {
+            minutes: 5,
+            seeds: 2,
+            strategy: Player::Roi,
+            clicks: 0,
+            rate: 0.5,
+            upgrades: Some(PathBuf::from("my_upgrades.json")),
+            report: Report::Curve,
+        }
+    );
+}
+
+#[test]
+fn bad_args() {
+    assert!(args(&["--minutes"]).is_err());
+    assert!(args(&["--minutes", "soon"]).is_err());
+    assert!(args(&["--strategy", "cheat"]).is_err());
+    assert!(args(&["--colour"]).is_err());
+}
+
+#[test]
+fn runs_are_repeatable() {
+    let options = Options {
+        minutes: 10,
+        ..Options::default()
+    };
+    let a = simulate(4, &options);
+    let b = simulate(4, &options);
+    assert!(!a.bought.is_empty());
+    assert_eq!(a.bought, b.bought);
+    assert_eq!(a.curve, b.curve);
+}
+
+#[test]
+fn no_clicks_buys_nothing() {
+    let options = Options {
+        minutes: 5,
+        clicks: 0,
+        ..Options::default()
+    };
+    let run = simulate(0, &options);
+    assert!(run.bought.is_empty());
+    assert_eq!(run.curve.len(), 6);
+    assert_eq!(run.curve.last().unwrap().cash, 0);
+}
+
+#[test]
+fn no_arrivals_only_first_tickets() {
+    let options = Options {
+        minutes: 5,
+        rate: 0.0,
+        ..Options::default()
+    };
+    let run = simulate(0, &options);
+    assert_eq!(run.curve.last().unwrap().tickets_closed, 4);
+}
+
+#[test]
+fn finished_once() {
+    let options = Options {
+        minutes: 60,
+        seeds: 1,
+        ..Options::default()
+    };
+    let run = simulate(0, &options);
+    let finished = run.all_bought.unwrap();
+    // repeatable upgrades keep being bought after the tree is finished
+    assert!(run.bought.iter().any(|(secs, _)| *secs > finished));
+    // it's when the last upgrade was bought for the first time
+    let mut first = std::collections::HashMap::new();
+    for (secs, id) in &run.bought {
+        first.entry(id).or_insert(*secs);
+    }
+    assert_eq!(first.values().max(), Some(&finished));
+}
+
+/// Which of the strategies in `strategy.rs` plays
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Player {
+    Random,
+    Greedy,
+    Roi,
+}
+
+/// Which CSV gets printed
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Report {
+    /// One row per run, with how many upgrades were bought and when the whole
+    /// tree was finished
+    Summary,
+    /// One row per upgrade bought
+    Upgrades,
+    /// One row per run per simulated minute
+    Curve,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Options {
+    minutes: i64,
+    seeds: u64,
+    strategy: Player,
+    clicks: u32,
+    /// Average new tickets per second
+    rate: f64,
+    /// Catalog to play with instead of the built in one
+    upgrades: Option<PathBuf>,
+    report: Report,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            minutes: 60,
+            seeds: 10,
+            strategy: Player::Greedy,
+            clicks: 3,
+            rate: GameState::ARRIVAL_RATE,
+            upgrades: None,
+            report: Report::Summary,
+        }
+    }
+}
+
+/// The player's progress at one point in a run
+#[derive(Debug, Clone, PartialEq)]
+struct Sample {
+    secs: i64,
+    cash: Amount,
+    xp: Amount,
+    tickets_closed: u64,
+    purchased: usize,
+}
+
+/// Everything recorded during one run
+#[derive(Debug, Clone, PartialEq)]
+struct Run {
+    seed: u64,
+    /// When each upgrade was bought, in the order they were bought
+    bought: Vec<(i64, String)>,
+    /// Progress at the start and at the end of every minute
+    curve: Vec<Sample>,
+    /// When the last upgrade in the catalog was bought
+    all_bought: Option<i64>,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options::default();
+    while let Some(flag) = args.next() {
+        let value = args
+            .next()
+            .ok_or_else(|| format!("{} needs a value", flag))?;
+        let number = || format!("{} needs a number, not {}", flag, value);
+        match flag.as_str() {
+            "--minutes" => options.minutes = value.parse().map_err(|_| number())?,
+            "--seeds" => options.seeds = value.parse().map_err(|_| number())?,
+            "--clicks" => options.clicks = value.parse().map_err(|_| number())?,
+            "--rate" => options.rate = value.parse().map_err(|_| number())?,
+            "--strategy" => {
+                options.strategy = match value.as_str() {
+                    "random" => Player::Random,
+                    "greedy" => Player::Greedy,
+                    "roi" => Player::Roi,
+                    _ => return Err(format!("Unknown strategy {}", value)),
+                }
+            }
+            "--upgrades" => options.upgrades = Some(PathBuf::from(value)),
+            "--report" => {
+                options.report = match value.as_str() {
+                    "summary" => Report::Summary,
+                    "upgrades" => Report::Upgrades,
+                    "curve" => Report::Curve,
+                    _ => return Err(format!("Unknown report {}", value)),
+                }
+            }
+            _ => return Err(format!("Unknown option {}", flag)),
+        }
+    }
+    Ok(options)
+}
+
+fn sample(game: &GameState, secs: i64) -> Sample {
+    Sample {
+        secs,
+        cash: game.wallet().cash(),
+        xp: game.wallet().xp(),
+        tickets_closed: game.tickets_closed(),
+        purchased: game.purchased().len(),
+    }
+}
+
+/// Play one game for `options.minutes`
+fn simulate(seed: u64, options: &Options) -> Run {
+    let mut game = GameState::with_seed(seed);
+    game.set_arrival_rate(options.rate);
+    game.init_queue();
+    let mut player: Box<dyn Strategy> = match options.strategy {
+        Player::Random => Box::new(strategy::RandomClicker::new(options.clicks, seed)),
+        Player::Greedy => Box::new(strategy::Greedy::new(options.clicks)),
+        Player::Roi => Box::new(strategy::Roi::new(options.clicks)),
+    };
+    let mut run = Run {
+        seed,
+        bought: Vec::new(),
+        curve: vec![sample(&game, 0)],
+        all_bought: None,
+    };
+
+    for secs in 1..=options.minutes * 60 {
+        for command in player.act(&game) {
+            let bought = match &command {
+                Command::BuyUpgrade(id) => Some(id.clone()),
+                _ => None,
+            };
+            if game.apply(command).is_err() {
+                continue;
+            }
+            if let Some(id) = bought {
+                run.bought.push((secs, id));
+                // later levels of repeatable upgrades don't finish it again
+                if run.all_bought.is_none() && game.purchased().len() == game.upgrades().len() {
+                    run.all_bought = Some(secs);
+                }
+            }
+        }
+        game.step(1);
+        if secs % 60 == 0 {
+            run.curve.push(sample(&game, secs));
+        }
+    }
+    run
+}
+
+fn print_report(runs: &[Run], report: Report) {
+    match report {
+        Report::Summary => {
+            println!("seed,upgrades_bought,all_bought_secs,cash,xp,tickets_closed");
+            for run in runs {
+                let last = run.curve.last().unwrap();
+                let all = run.all_bought.map(|x| x.to_string()).unwrap_or_default();
+                println!(
+                    "{},{},{},{},{},{}",
+                    run.seed,
+                    run.bought.len(),
+                    all,
+                    last.cash,
+                    last.xp,
+                    last.tickets_closed
+                );
+            }
+        }
+        Report::Upgrades => {
+            println!("seed,secs,upgrade");
+            for run in runs {
+                for (secs, id) in &run.bought {
+                    println!("{},{},{}", run.seed, secs, id);
+                }
+            }
+        }
+        Report::Curve => {
+            println!("seed,secs,cash,xp,tickets_closed,purchased");
+            for run in runs {
+                for x in &run.curve {
+                    println!(
+                        "{},{},{},{},{},{}",
+                        run.seed, x.secs, x.cash, x.xp, x.tickets_closed, x.purchased
+                    );
+                }
+            }
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let options = match parse_args(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+    if let Some(path) = &options.upgrades {
+        catalog::set_override(path.clone());
+    }
+    if let (_, Some(errors)) = catalog::loaded() {
+        eprintln!(
+            "Using the built in upgrades, the catalog file has problems: {:#?}",
+            errors
+        );
+    }
+    let runs: Vec<Run> = (0..options.seeds)
+        .map(|seed| simulate(seed, &options))
+        .collect();
+    print_report(&runs, options.report);
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,745 @@
+use crate::currency::*;
+use crate::game::GameState;
+use crate::promotion::Perk;
+use crate::ticket::*;
+use crate::upgrade::*;
+
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{OnceLock, RwLock};
+use std::time::SystemTime;
+
+#[cfg(test)]
+fn upgrade(id: &str, requires: &[&str], effects: Vec<Effects>) -> Upgrade {
+    Upgrade {
+        id: id.to_string(),
+        name: id.to_string(),
+        desc: String::new(),
+        cost: crate::currency::Currency::new(),
+        requires: requires.iter().map(|x| x.to_string()).collect(),
+        effects,
+        repeat: None,
+        reputation: None,
+    }
+}
+
+#[cfg(test)]
+fn errors(upgrades: &[Upgrade]) -> Vec<CatalogError> {
+    validate(upgrades).err().unwrap_or_default()
+}
+
+#[cfg(test)]
+fn temp_catalog(name: &str, json: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!("ticket-tycoon-{}.json", name));
+    fs::write(&path, json).unwrap();
+    path
+}
+
+#[test]
+fn embedded_valid() {
+    assert_eq!(validate(&embedded()), Ok(()));
+    assert!(embedded().iter().any(|x| x.id == "ergonomic_mousepad"));
+}
+
+#[test]
+fn embedded_perks_valid() {
+    assert_eq!(validate_perks(crate::promotion::perks()), Ok(()));
+}
+
+#[test]
+fn bad_perks() {
+    let json = r#"[
+        {"id": "mentor", "name": "", "desc": "", "cost": 1, "requires": [],
+         "effects": [{"IncMultiplier": 1.25}]},
+        {"id": "mentor", "name": "", "desc": "", "cost": 0, "requires": ["boss"],
+         "effects": [{"IncCashMultiplier": 0.0}]}
+    ]"#;
+    assert_eq!(
+        parse_perks(json).unwrap_err(),
+        vec![
+            CatalogError::DuplicateId("mentor".to_string()),
+            CatalogError::UnknownRequirement {
+                id: "mentor".to_string(),
+                requires: "boss".to_string(),
+            },
+            CatalogError::NonPositiveMultiplier {
+                id: "mentor".to_string(),
+                value: 0.0,
+            },
+            CatalogError::FreePerk("mentor".to_string()),
+        ]
+    );
+    // effects that aren't stats at all don't parse
+    let json = r#"[{"id": "luck", "name": "", "desc": "", "cost": 1, "requires": [],
+        "effects": [{"IncLuck": 2.0}]}]"#;
+    assert!(matches!(
+        parse_perks(json).unwrap_err()[..],
+        [CatalogError::Json(_)]
+    ));
+}
+
+#[test]
+fn load_good_override() {
+    let path = temp_catalog(
+        "good-override",
+        r#"[{"id": "desk", "name": "Desk", "desc": "", "cost": {"cash": 1, "xp": 0},
+            "requires": [], "effects": [{"IncMultiplier": 1.5}]}]"#,
+    );
+    let upgrades = load_from(&path).unwrap();
+    assert_eq!(upgrades.len(), 1);
+    assert_eq!(upgrades[0].id, "desk");
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn load_bad_override() {
+    let path = temp_catalog(
+        "bad-override",
+        r#"[{"id": "desk", "name": "Desk", "desc": "", "cost": {"cash": 1, "xp": 0},
+            "requires": ["chair"], "effects": [{"IncMultiplier": 0.0}]}]"#,
+    );
+    let errors = load_from(&path).unwrap_err();
+    assert_eq!(errors.len(), 3);
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn bad_override_falls_back() {
+    let path = temp_catalog(
+        "fallback-override",
+        r#"[{"id": "desk", "name": "Desk", "desc": "", "cost": {"cash": 1, "xp": 0},
+            "requires": ["chair"], "effects": [{"IncMultiplier": 1.5}]}]"#,
+    );
+    let (upgrades, errors) = load_or_embedded(Some(&path));
+    assert_eq!(upgrades, embedded());
+    assert_eq!(
+        errors,
+        Some(vec![
+            CatalogError::UnknownRequirement {
+                id: "desk".to_string(),
+                requires: "chair".to_string(),
+            },
+            CatalogError::Unreachable("desk".to_string())
+        ])
+    );
+    assert_eq!(load_or_embedded(None), (embedded(), None));
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn missing_file() {
+    let errors = read(Path::new("src/no_such_upgrades.json")).unwrap_err();
+    assert!(matches!(errors[..], [CatalogError::Io(_)]));
+}
+
+#[test]
+fn bad_json() {
+    let errors = parse("[{\"id\": \"mouse\"}]").unwrap_err();
+    assert!(matches!(errors[..], [CatalogError::Json(_)]));
+}
+
+#[test]
+fn duplicate_id() {
+    let upgrades = [
+        upgrade("mouse", &[], vec![Effects::IncMultiplier(1.1)]),
+        upgrade("mouse", &[], vec![Effects::IncMultiplier(1.2)]),
+    ];
+    assert_eq!(
+        errors(&upgrades),
+        vec![CatalogError::DuplicateId("mouse".to_string())]
+    );
+}
+
+#[test]
+fn unknown_requirement() {
+    let upgrades = [upgrade(
+        "mouse",
+        &["desk"],
+        vec![Effects::IncMultiplier(1.1)],
+    )];
+    assert_eq!(
+        errors(&upgrades),
+        vec![
+            CatalogError::UnknownRequirement {
+                id: "mouse".to_string(),
+                requires: "desk".to_string()
+            },
+            CatalogError::Unreachable("mouse".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn cycle() {
+    let upgrades = [
+        upgrade("start", &[], vec![Effects::IncMultiplier(1.1)]),
+        upgrade("a", &["start", "c"], vec![Effects::IncMultiplier(1.1)]),
+        upgrade("b", &["a"], vec![Effects::IncMultiplier(1.1)]),
+        upgrade("c", &["b"], vec![Effects::IncMultiplier(1.1)]),
+        upgrade("d", &["c"], vec![Effects::IncMultiplier(1.1)]),
+    ];
+    let errors = errors(&upgrades);
+    let cycle = ["a", "c", "b"].map(String::from).to_vec();
+    assert!(errors.contains(&CatalogError::Cycle(cycle)));
+    for id in ["a", "b", "c", "d"] {
+        assert!(errors.contains(&CatalogError::Unreachable(id.to_string())));
+    }
+    assert_eq!(errors.len(), 5);
+}
+
+#[test]
+fn requires_itself() {
+    let upgrades = [upgrade("a", &["a"], vec![Effects::IncMultiplier(1.1)])];
+    assert!(errors(&upgrades).contains(&CatalogError::Cycle(vec!["a".to_string()])));
+}
+
+#[test]
+fn bad_multipliers() {
+    let upgrades = [
+        upgrade("zero", &[], vec![Effects::IncMultiplier(0.0)]),
+        upgrade("negative", &[], vec![Effects::IncCashMultiplier(-1.2)]),
+        upgrade("nan", &[], vec![Effects::IncXPMultiplier(f32::NAN)]),
+        upgrade("same", &[], vec![Effects::IncMultiplier(1.0)]),
+        upgrade("worse", &[], vec![Effects::IncXPMultiplier(0.9)]),
+        upgrade(
+            "huge",
+            &[],
+            vec![Effects::IncPriorityMultiplier(Priority::P1, 50.0)],
+        ),
+    ];
+    let errors = errors(&upgrades);
+    let bad: Vec<&str> = errors
+        .iter()
+        .filter_map(|x| match x {
+            CatalogError::NonPositiveMultiplier { id, .. } => Some(id.as_str()),
+            _ => None,
+        })
+        .collect();
+    let odd: Vec<&str> = errors
+        .iter()
+        .filter_map(|x| match x {
+            CatalogError::SuspiciousMultiplier { id, .. } => Some(id.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(bad, vec!["zero", "negative", "nan"]);
+    assert_eq!(odd, vec!["same", "worse", "huge"]);
+}
+
+#[test]
+fn duplicate_autosolve() {
+    let upgrades = [
+        upgrade(
+            "a",
+            &[],
+            vec![Effects::AutoSolve(Difficulty::Easy, Category::Web)],
+        ),
+        upgrade(
+            "b",
+            &[],
+            vec![Effects::AutoSolve(Difficulty::Easy, Category::Misc)],
+        ),
+        upgrade(
+            "c",
+            &[],
+            vec![Effects::AutoSolve(Difficulty::Easy, Category::Web)],
+        ),
+    ];
+    assert_eq!(
+        errors(&upgrades),
+        vec![CatalogError::DuplicateAutoSolve {
+            ids: vec!["a".to_string(), "c".to_string()],
+            difficulty: Difficulty::Easy,
+            category: Category::Web,
+        }]
+    );
+}
+
+#[test]
+fn bad_repeats() {
+    let repeat = |id, cost, max, effect| {
+        let mut upgrade = upgrade(id, &[], vec![effect]);
+        upgrade.repeat = Some(Repeat { max, cost });
+        upgrade
+    };
+    let faster = Effects::IncMultiplier(1.1);
+    let upgrades = [
+        repeat("fine", CostCurve::Exponential(1.2), None, faster.clone()),
+        repeat("cheaper", CostCurve::Exponential(0.5), None, faster.clone()),
+        repeat("empty", CostCurve::Table(Vec::new()), None, faster.clone()),
+        repeat("never", CostCurve::Exponential(1.2), Some(0), faster),
+        repeat(
+            "autoclose",
+            CostCurve::Exponential(1.2),
+            Some(3),
+            Effects::AutoClose,
+        ),
+    ];
+    assert_eq!(
+        errors(&upgrades),
+        vec![
+            CatalogError::BadRepeat("cheaper".to_string()),
+            CatalogError::BadRepeat("empty".to_string()),
+            CatalogError::BadRepeat("never".to_string()),
+            CatalogError::UnstackableRepeat("autoclose".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn too_much_reputation() {
+    let mut trusted = upgrade("trusted", &[], vec![Effects::IncMultiplier(1.1)]);
+    trusted.reputation = Some(GameState::MAX_REPUTATION);
+    let mut legend = upgrade("legend", &[], vec![Effects::IncMultiplier(1.1)]);
+    legend.reputation = Some(GameState::MAX_REPUTATION + 1);
+    assert_eq!(
+        errors(&[trusted, legend]),
+        vec![CatalogError::TooMuchReputation {
+            id: "legend".to_string(),
+            reputation: GameState::MAX_REPUTATION + 1,
+        }]
+    );
+}
+
+#[test]
+fn spends_reputation() {
+    let repeat = |id, cost| {
+        let mut upgrade = upgrade(id, &[], vec![Effects::IncMultiplier(1.1)]);
+        upgrade.repeat = Some(Repeat { max: None, cost });
+        upgrade
+    };
+    let reputation = Currency::new().with(Resource::Reputation, 10);
+    let mut bribe = upgrade("bribe", &[], vec![Effects::IncMultiplier(1.1)]);
+    bribe.cost = reputation.clone();
+    let upgrades = [
+        bribe,
+        repeat("fine", CostCurve::Exponential(1.2)),
+        repeat("linear", CostCurve::Linear(reputation.clone())),
+        repeat("table", CostCurve::Table(vec![Currency::new(), reputation])),
+    ];
+    assert_eq!(
+        errors(&upgrades),
+        vec![
+            CatalogError::SpendsReputation("bribe".to_string()),
+            CatalogError::SpendsReputation("linear".to_string()),
+            CatalogError::SpendsReputation("table".to_string()),
+        ]
+    );
+}
+
+#[cfg(test)]
+fn touch(path: &Path, secs: u64) {
+    let time = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(secs);
+    fs::File::options()
+        .write(true)
+        .open(path)
+        .unwrap()
+        .set_modified(time)
+        .unwrap();
+}
+
+#[test]
+fn watch_changes() {
+    let desk = r#"[{"id": "desk", "name": "Desk", "desc": "", "cost": {"cash": 1, "xp": 0},
+        "requires": [], "effects": [{"IncMultiplier": 1.5}]}]"#;
+    let path = temp_catalog("watched", desk);
+    touch(&path, 1_700_000_000);
+    let mut watcher = Watcher::new(path.clone());
+    assert_eq!(watcher.poll(), None);
+
+    fs::write(&path, desk.replace("1.5", "2.0")).unwrap();
+    touch(&path, 1_700_000_010);
+    let upgrades = watcher.poll().unwrap().unwrap();
+    assert_eq!(upgrades[0].effects, vec![Effects::IncMultiplier(2.0)]);
+    assert_eq!(watcher.poll(), None);
+
+    fs::write(&path, desk.replace("1.5", "0.0")).unwrap();
+    touch(&path, 1_700_000_020);
+    assert!(watcher.poll().unwrap().is_err());
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn watch_missing_file() {
+    let mut watcher = Watcher::new(env::temp_dir().join("ticket-tycoon-not-there.json"));
+    assert_eq!(watcher.poll(), None);
+}
+
+/// Something wrong with an upgrade catalog
+#[derive(Debug, Clone, PartialEq)]
+pub enum CatalogError {
+    /// Couldn't read the file
+    Io(String),
+    /// The file isn't a valid list of upgrades
+    Json(String),
+    /// More than one upgrade has this ID
+    DuplicateId(String),
+    /// `id` requires an upgrade that isn't in the catalog
+    UnknownRequirement { id: String, requires: String },
+    /// These upgrades require each other in a loop, in requirement order
+    Cycle(Vec<String>),
+    /// This upgrade can never be bought, because something it requires
+    /// (directly or not) is unknown or part of a cycle
+    Unreachable(String),
+    /// A multiplier that is zero, negative or NaN, which would wreck every
+    /// later multiplier too
+    NonPositiveMultiplier { id: String, value: f32 },
+    /// A multiplier that makes things no better (at most 1) or that is
+    /// probably a typo (over `MAX_MULTIPLIER`)
+    SuspiciousMultiplier { id: String, value: f32 },
+    /// More than one upgrade turns on autosolve for the same tickets
+    DuplicateAutoSolve {
+        ids: Vec<String>,
+        difficulty: Difficulty,
+        category: Category,
+    },
+    /// A repeatable upgrade whose price goes down, that has an empty price
+    /// table, or that can't be bought at all
+    BadRepeat(String),
+    /// A repeatable upgrade with effects that do nothing when bought again
+    UnstackableRepeat(String),
+    /// Needs more reputation than the player can ever have
+    TooMuchReputation { id: String, reputation: u64 },
+    /// A price that includes reputation, which is only ever needed, not spent
+    SpendsReputation(String),
+    /// A perk that costs no seniority
+    FreePerk(String),
+}
+
+/// The catalog that ships with the game, built into the binary so it doesn't
+/// matter where the game is run from
+const EMBEDDED: &str = include_str!("upgrades.json");
+
+/// Environment variable that can point at a different catalog file, for
+/// modding and balancing
+pub const OVERRIDE_VAR: &str = "TICKET_TYCOON_UPGRADES";
+
+/// Catalog file given on the command line, takes priority over `OVERRIDE_VAR`
+static OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+
+/// The catalog being played with, loaded the first time it's needed, and the
+/// problems with the override file if it couldn't be used
+static LOADED: RwLock<Option<Loaded>> = RwLock::new(None);
+
+/// Upgrades, and the problems with the catalog file they should have come from
+/// if the built in ones had to be used instead
+pub type Loaded = (Vec<Upgrade>, Option<Vec<CatalogError>>);
+
+/// Largest multiplier a single effect should have
+pub const MAX_MULTIPLIER: f32 = 3.0;
+
+/// Read a catalog from a JSON file, without checking it
+pub fn read(path: &Path) -> Result<Vec<Upgrade>, Vec<CatalogError>> {
+    let json = fs::read_to_string(path).map_err(|x| vec![CatalogError::Io(x.to_string())])?;
+    parse(&json)
+}
+
+/// Parse a catalog from JSON, without checking it
+pub fn parse(json: &str) -> Result<Vec<Upgrade>, Vec<CatalogError>> {
+    serde_json::from_str(json).map_err(|x| vec![CatalogError::Json(x.to_string())])
+}
+
+/// The catalog that ships with the game
+pub fn embedded() -> Vec<Upgrade> {
+    parse(EMBEDDED).expect("Built in upgrades.json should parse, the tests check it")
+}
+
+/// Play with the catalog at `path` instead of the built in one
+/// Has to be called before the catalog is first loaded to make a difference
+pub fn set_override(path: PathBuf) {
+    let _ = OVERRIDE.set(path);
+}
+
+/// Where the catalog should be loaded from instead of the built in one, if
+/// anywhere
+fn override_path() -> Option<PathBuf> {
+    OVERRIDE
+        .get()
+        .cloned()
+        .or_else(|| env::var_os(OVERRIDE_VAR).map(PathBuf::from))
+}
+
+/// Read a catalog file and check it
+pub fn load_from(path: &Path) -> Result<Vec<Upgrade>, Vec<CatalogError>> {
+    let upgrades = read(path)?;
+    validate(&upgrades)?;
+    Ok(upgrades)
+}
+
+/// The catalog at `path` if there is one and it's valid, otherwise the built
+/// in catalog along with what's wrong with `path`
+fn load_or_embedded(path: Option<&Path>) -> Loaded {
+    match path.map(load_from) {
+        Some(Ok(upgrades)) => (upgrades, None),
+        Some(Err(errors)) => (embedded(), Some(errors)),
+        None => (embedded(), None),
+    }
+}
+
+/// The catalog the game is played with
+/// This is the override file if there is one and it's valid, otherwise it's
+/// the built in catalog, unless it's been replaced since
+/// If the override file couldn't be used its problems come back too, for the
+/// caller to report
+pub fn loaded() -> Loaded {
+    if let Some(loaded) = LOADED.read().unwrap().as_ref() {
+        return loaded.clone();
+    }
+    LOADED
+        .write()
+        .unwrap()
+        .get_or_insert_with(|| load_or_embedded(override_path().as_deref()))
+        .clone()
+}
+
+/// Play with a different catalog from now on, e.g. after it was edited
+/// Games that are already running keep theirs, see `GameState::reload_upgrades`
+pub fn replace(upgrades: Vec<Upgrade>) {
+    *LOADED.write().unwrap() = Some((upgrades, None));
+}
+
+/// The file to watch for changes while the game is running, if any
+/// That's the override file, or in debug builds the catalog in the source tree
+pub fn watched_path() -> Option<PathBuf> {
+    override_path().or_else(|| {
+        cfg!(debug_assertions)
+            .then(|| PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/src/upgrades.json")))
+    })
+}
+
+/// Notices when a catalog file changes, by checking when it was last modified
+#[derive(Debug, Clone, PartialEq)]
+pub struct Watcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+}
+
+impl Watcher {
+    /// Start watching `path`, the way it is now doesn't count as a change
+    pub fn new(path: PathBuf) -> Self {
+        let modified = modified(&path);
+        Self { path, modified }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// If the file has changed since the last call, read and check it again
+    pub fn poll(&mut self) -> Option<Result<Vec<Upgrade>, Vec<CatalogError>>> {
+        let modified = modified(&self.path);
+        if modified.is_none() || modified == self.modified {
+            return None;
+        }
+        self.modified = modified;
+        Some(load_from(&self.path))
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|x| x.modified()).ok()
+}
+
+/// Check a catalog for mistakes
+/// Returns every problem found, in the order the upgrades are listed
+pub fn validate(upgrades: &[Upgrade]) -> Result<(), Vec<CatalogError>> {
+    let mut errors = Vec::new();
+    let mut by_id: HashMap<&str, &Upgrade> = HashMap::new();
+    for upgrade in upgrades {
+        if by_id.insert(&upgrade.id, upgrade).is_some() {
+            errors.push(CatalogError::DuplicateId(upgrade.id.clone()));
+        }
+    }
+
+    for upgrade in upgrades {
+        for req in &upgrade.requires {
+            if !by_id.contains_key(req.as_str()) {
+                errors.push(CatalogError::UnknownRequirement {
+                    id: upgrade.id.clone(),
+                    requires: req.clone(),
+                });
+            }
+        }
+    }
+
+    errors.extend(cycles(upgrades, &by_id));
+
+    // buy everything that can be bought until nothing else can be
+    let mut bought: HashSet<&str> = HashSet::new();
+    loop {
+        let before = bought.len();
+        for upgrade in upgrades {
+            if upgrade.requires.iter().all(|x| bought.contains(x.as_str())) {
+                bought.insert(&upgrade.id);
+            }
+        }
+        if bought.len() == before {
+            break;
+        }
+    }
+    for upgrade in upgrades {
+        if !bought.contains(upgrade.id.as_str()) {
+            errors.push(CatalogError::Unreachable(upgrade.id.clone()));
+        }
+    }
+
+    for upgrade in upgrades {
+        for effect in &upgrade.effects {
+            let value = match effect {
+                Effects::IncMultiplier(x)
+                | Effects::IncCashMultiplier(x)
+                | Effects::IncXPMultiplier(x)
+                | Effects::IncPriorityMultiplier(_, x) => *x,
+                Effects::AutoSolve(..) | Effects::AutoClose => continue,
+            };
+            let id = upgrade.id.clone();
+            if value.is_nan() || value <= 0.0 {
+                errors.push(CatalogError::NonPositiveMultiplier { id, value });
+            } else if value <= 1.0 || value > MAX_MULTIPLIER {
+                errors.push(CatalogError::SuspiciousMultiplier { id, value });
+            }
+        }
+    }
+
+    let mut autosolve: Vec<((Difficulty, Category), Vec<String>)> = Vec::new();
+    for upgrade in upgrades {
+        for effect in &upgrade.effects {
+            if let Effects::AutoSolve(diff, cat) = effect {
+                match autosolve.iter_mut().find(|(x, _)| *x == (*diff, *cat)) {
+                    Some((_, ids)) => ids.push(upgrade.id.clone()),
+                    None => autosolve.push(((*diff, *cat), vec![upgrade.id.clone()])),
+                }
+            }
+        }
+    }
+    for ((difficulty, category), ids) in autosolve {
+        if ids.len() > 1 {
+            errors.push(CatalogError::DuplicateAutoSolve {
+                ids,
+                difficulty,
+                category,
+            });
+        }
+    }
+
+    for upgrade in upgrades {
+        let Some(repeat) = &upgrade.repeat else {
+            continue;
+        };
+        let bad = match &repeat.cost {
+            CostCurve::Exponential(factor) => factor.is_nan() || *factor < 1.0,
+            CostCurve::Table(prices) => prices.is_empty(),
+            CostCurve::Linear(_) => false,
+        };
+        if bad || repeat.max == Some(0) {
+            errors.push(CatalogError::BadRepeat(upgrade.id.clone()));
+        }
+        let stacks = |x: &Effects| !matches!(x, Effects::AutoSolve(..) | Effects::AutoClose);
+        if !upgrade.effects.iter().all(stacks) {
+            errors.push(CatalogError::UnstackableRepeat(upgrade.id.clone()));
+        }
+    }
+
+    for upgrade in upgrades {
+        if let Some(reputation) = upgrade
+            .reputation
+            .filter(|x| *x > GameState::MAX_REPUTATION)
+        {
+            errors.push(CatalogError::TooMuchReputation {
+                id: upgrade.id.clone(),
+                reputation,
+            });
+        }
+        let mut prices = vec![&upgrade.cost];
+        match upgrade.repeat.as_ref().map(|x| &x.cost) {
+            Some(CostCurve::Linear(step)) => prices.push(step),
+            Some(CostCurve::Table(table)) => prices.extend(table),
+            Some(CostCurve::Exponential(_)) | None => {}
+        }
+        if prices.iter().any(|x| x.get(Resource::Reputation) > 0) {
+            errors.push(CatalogError::SpendsReputation(upgrade.id.clone()));
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Read a perk catalog from JSON and check it
+pub fn parse_perks(json: &str) -> Result<Vec<Perk>, Vec<CatalogError>> {
+    let perks: Vec<Perk> =
+        serde_json::from_str(json).map_err(|x| vec![CatalogError::Json(x.to_string())])?;
+    validate_perks(&perks)?;
+    Ok(perks)
+}
+
+/// Check a perk catalog for the same mistakes as an upgrade catalog, and for
+/// perks that are free
+pub fn validate_perks(perks: &[Perk]) -> Result<(), Vec<CatalogError>> {
+    let upgrades: Vec<Upgrade> = perks
+        .iter()
+        .map(|perk| Upgrade {
+            id: perk.id.clone(),
+            name: perk.name.clone(),
+            desc: perk.desc.clone(),
+            cost: Currency::new(),
+            requires: perk.requires.clone(),
+            effects: perk.effects.clone(),
+            repeat: None,
+            reputation: None,
+        })
+        .collect();
+    let mut errors = validate(&upgrades).err().unwrap_or_default();
+    for perk in perks.iter().filter(|x| x.cost == 0) {
+        errors.push(CatalogError::FreePerk(perk.id.clone()));
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Find each loop of requirements once
+/// Depth first search, a requirement that is already on the current path
+/// closes a loop
+fn cycles<'a>(upgrades: &'a [Upgrade], by_id: &HashMap<&str, &'a Upgrade>) -> Vec<CatalogError> {
+    fn visit<'a>(
+        id: &'a str,
+        by_id: &HashMap<&str, &'a Upgrade>,
+        path: &mut Vec<&'a str>,
+        done: &mut HashSet<&'a str>,
+        found: &mut Vec<CatalogError>,
+    ) {
+        if done.contains(id) {
+            return;
+        }
+        if let Some(start) = path.iter().position(|x| *x == id) {
+            let cycle = path[start..].iter().map(|x| x.to_string()).collect();
+            found.push(CatalogError::Cycle(cycle));
+            return;
+        }
+        let Some(&upgrade) = by_id.get(id) else {
+            return;
+        };
+        path.push(id);
+        for req in &upgrade.requires {
+            visit(req, by_id, path, done, found);
+        }
+        path.pop();
+        done.insert(id);
+    }
+
+    let mut done = HashSet::new();
+    let mut found = Vec::new();
+    for upgrade in upgrades {
+        visit(&upgrade.id, by_id, &mut Vec::new(), &mut done, &mut found);
+    }
+    found
+}
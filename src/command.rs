@@ -0,0 +1,140 @@
+use crate::game::*;
+use crate::promotion::*;
+
+#[test]
+fn click_command() {
+    let mut game = GameState::with_seed(1);
+    game.init_queue();
+    let clicked = game.working()[0].clicked();
+    assert!(game.apply(Command::ClickTicket(0)).is_ok());
+    assert!(game.working()[0].clicked() > clicked);
+}
+
+#[test]
+fn click_missing_ticket() {
+    let mut game = GameState::with_seed(1);
+    assert!(
+        game.apply(Command::ClickTicket(0))
+            .is_err_and(|x| matches!(x, CommandError::NoTicket(0)))
+    );
+}
+
+#[test]
+fn buy_command() {
+    let mut game = GameState::with_seed(1);
+    game.init_queue();
+    let id = "wrist_stretch_reminder".to_string();
+    assert!(
+        game.apply(Command::BuyUpgrade(id.clone()))
+            .is_err_and(|x| matches!(x, CommandError::Buy(_)))
+    );
+    while game.wallet().xp() < 30 {
+        let _ = game.apply(Command::ClickTicket(0));
+        game.step(1);
+    }
+    assert!(game.apply(Command::BuyUpgrade(id.clone())).is_ok());
+    assert!(game.purchased().contains(&id));
+}
+
+#[test]
+fn rounding_command() {
+    let mut game = GameState::with_seed(1);
+    game.apply(Command::SetRounding(Rounding::Carry)).unwrap();
+    assert_eq!(game.rounding(), Rounding::Carry);
+}
+
+#[test]
+fn promotion_commands() {
+    let mut game = GameState::with_seed(1);
+    assert!(
+        game.apply(Command::Promote)
+            .is_err_and(|x| matches!(x, CommandError::Promotion(_)))
+    );
+    assert!(
+        game.apply(Command::BuyPerk("mentor".to_string()))
+            .is_err_and(|x| matches!(x, CommandError::Promotion(_)))
+    );
+}
+
+#[test]
+fn scripted_run() {
+    let commands = [
+        Command::ClickTicket(0),
+        Command::ClickTicket(1),
+        Command::BuyUpgrade("wrist_stretch_reminder".to_string()),
+        Command::ClickTicket(0),
+    ];
+    let run = || {
+        let mut game = GameState::with_seed(11);
+        game.init_queue();
+        for _ in 0..50 {
+            for command in commands.iter().cloned() {
+                let _ = game.apply(command);
+            }
+            game.step(1);
+        }
+        game
+    };
+    let (a, b) = (run(), run());
+    assert!(a.tickets_closed() > 0);
+    assert_eq!(a.wallet(), b.wallet());
+    assert_eq!(a.working(), b.working());
+}
+
+/// Something a player (or a bot) can do to the game
+/// Everything that changes the game apart from time passing goes through one
+/// of these, see `GameState::apply` and `GameState::step`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    /// Click on the ticket at this index of the working set
+    ClickTicket(usize),
+    /// Buy the upgrade with this ID
+    BuyUpgrade(String),
+    /// Change how fractional clicks and payouts are rounded
+    SetRounding(Rounding),
+    /// Trade in the run for seniority and start again
+    Promote,
+    /// Spend seniority on the perk with this ID
+    BuyPerk(String),
+}
+
+#[derive(Debug)]
+pub enum CommandError {
+    /// There's no ticket at that index of the working set
+    NoTicket(usize),
+    Buy(BuyError),
+    Promotion(PromotionError),
+}
+
+impl From<BuyError> for CommandError {
+    fn from(err: BuyError) -> Self {
+        CommandError::Buy(err)
+    }
+}
+
+impl From<PromotionError> for CommandError {
+    fn from(err: PromotionError) -> Self {
+        CommandError::Promotion(err)
+    }
+}
+
+impl GameState {
+    /// Do what the command says
+    pub fn apply(&mut self, command: Command) -> Result<(), CommandError> {
+        match command {
+            Command::ClickTicket(i) => {
+                if i >= self.working().len() {
+                    return Err(CommandError::NoTicket(i));
+                }
+                self.click_ticket(i);
+            }
+            Command::BuyUpgrade(id) => self.buy_upgrade(&id)?,
+            Command::SetRounding(rounding) => self.set_rounding(rounding),
+            Command::Promote => {
+                self.promote()?;
+            }
+            Command::BuyPerk(id) => self.buy_perk(&id)?,
+        }
+        Ok(())
+    }
+}
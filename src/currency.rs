@@ -1,4 +1,9 @@
-use serde::{Deserialize, Serialize};
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::{Add, AddAssign, Mul, Sub};
 
 #[test]
 fn empty() {
@@ -34,10 +39,11 @@ fn happy_rm_cash() {
 #[test]
 fn failed_rm_cash() {
     let mut wallet = Currency::new();
+    wallet.add_cash(4);
     assert!(wallet.rm_cash(10).is_err_and(|x| match x {
-        WalletError::InsufficientCash => true,
-        WalletError::InsufficientXP => false,
+        WalletError::Insufficient { kind, short } => kind == Resource::Cash && short == 6,
     }));
+    assert_eq!(wallet.cash(), 4);
 }
 
 #[test]
@@ -52,78 +58,442 @@ fn happy_rm_xp() {
 fn failed_rm_xp() {
     let mut wallet = Currency::new();
     assert!(wallet.rm_xp(10).is_err_and(|x| match x {
-        WalletError::InsufficientCash => false,
-        WalletError::InsufficientXP => true,
+        WalletError::Insufficient { kind, short } => kind == Resource::Xp && short == 10,
     }));
 }
 
-#[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq)]
-pub struct Currency {
-    cash: u64,
-    xp: u64,
+#[test]
+fn spend_all_or_nothing() {
+    let mut wallet = Currency::new();
+    wallet.add_cash(100);
+    wallet.add_xp(5);
+    let price = Currency::new()
+        .with(Resource::Cash, 50)
+        .with(Resource::Xp, 8);
+    assert!(wallet.spend(&price).is_err_and(|x| match x {
+        WalletError::Insufficient { kind, short } => kind == Resource::Xp && short == 3,
+    }));
+    assert_eq!(wallet.cash(), 100);
+    wallet.add_xp(3);
+    assert!(wallet.spend(&price).is_ok());
+    assert_eq!(wallet, Currency::new().with(Resource::Cash, 50));
+}
+
+#[test]
+fn zero_amounts_ignored() {
+    let mut wallet = Currency::new();
+    wallet.add_cash(0);
+    wallet.add_xp(5);
+    wallet.rm_xp(5).unwrap();
+    assert_eq!(wallet, Currency::new());
+    let parsed: Currency = serde_json::from_str("{\"cash\":0,\"xp\":0}").unwrap();
+    assert_eq!(parsed, Currency::new());
+    assert_eq!(serde_json::to_string(&parsed).unwrap(), "{}");
+}
+
+#[test]
+fn short_currency() {
+    assert_eq!(Currency::new().short(), "");
+    let price = Currency::new()
+        .with(Resource::Cash, 1500)
+        .with(Resource::Xp, 40);
+    assert_eq!(price.short(), "$1.5K and 40 XP");
+    assert_eq!(Currency::new().with(Resource::Xp, 40).short(), "40 XP");
+    assert_eq!(
+        Currency::new().with(Resource::Reputation, 25).short(),
+        "25 reputation"
+    );
+}
+
+#[test]
+fn add_saturates() {
+    let mut wallet = Currency::new();
+    wallet.add_cash(u64::MAX);
+    wallet.add_cash(Amount::MAX);
+    wallet.add_cash(1);
+    assert_eq!(wallet.cash(), Amount::MAX);
+}
+
+#[test]
+fn past_u64() {
+    let mut wallet = Currency::new();
+    wallet.add_cash(u64::MAX);
+    wallet.add_cash(u64::MAX);
+    assert!(wallet.cash() > u64::MAX);
+    assert!(wallet.rm_cash(u64::MAX).is_ok());
+    assert_eq!(wallet.cash(), u64::MAX);
+}
+
+#[test]
+fn old_json() {
+    let wallet: Currency = serde_json::from_str("{\"cash\":3100,\"xp\":520}").unwrap();
+    assert_eq!(wallet.cash(), 3100);
+    assert_eq!(wallet.xp(), 520);
+    assert_eq!(
+        serde_json::to_string(&wallet).unwrap(),
+        "{\"cash\":3100,\"xp\":520}"
+    );
+}
+
+#[test]
+fn big_json() {
+    let mut wallet = Currency::new();
+    wallet.add_cash(Amount::from(u64::MAX) * 1000);
+    // saves are migrated as a serde_json::Value, which can't hold a u128
+    let value = serde_json::to_value(&wallet).unwrap();
+    let loaded: Currency = serde_json::from_value(value).unwrap();
+    assert_eq!(loaded, wallet);
+    let json = serde_json::to_string(&Amount::MAX).unwrap();
+    assert_eq!(json, format!("\"{}\"", u128::MAX));
+    assert_eq!(serde_json::from_str::<Amount>(&json).unwrap(), Amount::MAX);
+    // older saves wrote big amounts as floats, those still load
+    let float: Amount = serde_json::from_str("1.5e20").unwrap();
+    assert_eq!(float, Amount::from(15) * 10_000_000_000_000_000_000);
+    assert!(serde_json::from_str::<Currency>("{\"cash\":-5,\"xp\":0}").is_err());
+    assert!(serde_json::from_str::<Amount>("\"-5\"").is_err());
+    assert!(serde_json::from_str::<Amount>("\"lots\"").is_err());
+}
+
+#[test]
+fn short_amounts() {
+    let short = |n: u64| Amount::from(n).short();
+    assert_eq!(short(0), "0");
+    assert_eq!(short(950), "950");
+    assert_eq!(short(1_234), "1.2K");
+    assert_eq!(short(3_460_000), "3.5M");
+    assert_eq!(short(999_999), "1.0M");
+    assert_eq!(short(7_800_000_000), "7.8B");
+    assert_eq!(short(5_600_000_000_000), "5.6e12");
+    assert_eq!(Amount::MAX.short(), "3.4e38");
+}
+
+/// An amount of one currency
+/// It's a u128 so there's plenty of room for late game numbers, and adding or
+/// multiplying stops at the largest amount instead of overflowing. In JSON
+/// amounts that fit in a u64 are plain numbers, so saves from when amounts were
+/// u64 load as they are, and bigger ones are strings of digits. Either form
+/// loads back
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Amount(u128);
+
+impl Amount {
+    pub const ZERO: Self = Self(0);
+    pub const MAX: Self = Self(u128::MAX);
+
+    /// Returns None if `n` is more than this
+    pub fn checked_sub(self, n: Self) -> Option<Self> {
+        self.0.checked_sub(n.0).map(Self)
+    }
+
+    /// Multiply by `x` and round to the nearest whole amount
+    /// Anything too big is the largest amount, and negative or NaN is 0
+    pub fn scale(self, x: f64) -> Self {
+        Self((self.0 as f64 * x).round() as u128)
+    }
+
+    /// Close enough for working out rates and ratios
+    pub fn as_f64(self) -> f64 {
+        self.0 as f64
+    }
+
+    /// Short form to show the player, e.g. 950, 1.2K, 3.4M, 7.8B, 5.6e12
+    pub fn short(self) -> String {
+        if self.0 < 1000 {
+            return self.0.to_string();
+        }
+        let x = self.as_f64();
+        for (unit, size) in [("K", 1e3), ("M", 1e6), ("B", 1e9)] {
+            // 999.95 would round up to 1000.0
+            if x / size < 999.95 {
+                return format!("{:.1}{}", x / size, unit);
+            }
+        }
+        format!("{:.1e}", x)
+    }
+}
+
+impl From<u64> for Amount {
+    fn from(n: u64) -> Self {
+        Self(n as u128)
+    }
+}
+
+impl Add for Amount {
+    type Output = Self;
+
+    fn add(self, n: Self) -> Self {
+        Self(self.0.saturating_add(n.0))
+    }
+}
+
+impl AddAssign for Amount {
+    fn add_assign(&mut self, n: Self) {
+        *self = *self + n;
+    }
+}
+
+/// Stops at 0 rather than going negative
+impl Sub for Amount {
+    type Output = Self;
+
+    fn sub(self, n: Self) -> Self {
+        Self(self.0.saturating_sub(n.0))
+    }
+}
+
+impl Mul<u64> for Amount {
+    type Output = Self;
+
+    fn mul(self, n: u64) -> Self {
+        Self(self.0.saturating_mul(n as u128))
+    }
+}
+
+impl PartialEq<u64> for Amount {
+    fn eq(&self, n: &u64) -> bool {
+        self.0 == *n as u128
+    }
+}
+
+impl PartialOrd<u64> for Amount {
+    fn partial_cmp(&self, n: &u64) -> Option<Ordering> {
+        self.0.partial_cmp(&(*n as u128))
+    }
+}
+
+/// The exact amount, see `short` for something more readable
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Amounts that fit in a u64 are written as normal integers. Anything bigger
+/// is written as a string of digits, since `serde_json::Value` (which saves go
+/// through to be migrated) would turn bigger integers into rounded floats
+impl Serialize for Amount {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match u64::try_from(self.0) {
+            Ok(n) => serializer.serialize_u64(n),
+            Err(_) => serializer.serialize_str(&self.0.to_string()),
+        }
+    }
+}
+
+/// Takes integers, strings of digits, and the floats that saves used for big
+/// amounts before they were written as strings
+impl<'de> Deserialize<'de> for Amount {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct AmountVisitor;
+
+        impl Visitor<'_> for AmountVisitor {
+            type Value = Amount;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "a non-negative number, or a string of digits")
+            }
+
+            fn visit_u64<E: de::Error>(self, n: u64) -> Result<Amount, E> {
+                Ok(Amount::from(n))
+            }
+
+            fn visit_u128<E: de::Error>(self, n: u128) -> Result<Amount, E> {
+                Ok(Amount(n))
+            }
+
+            fn visit_i64<E: de::Error>(self, n: i64) -> Result<Amount, E> {
+                u64::try_from(n)
+                    .map(Amount::from)
+                    .map_err(|_| E::invalid_value(de::Unexpected::Signed(n), &self))
+            }
+
+            fn visit_f64<E: de::Error>(self, n: f64) -> Result<Amount, E> {
+                if n.is_nan() || n < 0.0 {
+                    return Err(E::invalid_value(de::Unexpected::Float(n), &self));
+                }
+                Ok(Amount(n as u128))
+            }
+
+            fn visit_str<E: de::Error>(self, s: &str) -> Result<Amount, E> {
+                // u128's parser would also take a leading +
+                if s.is_empty() || !s.bytes().all(|x| x.is_ascii_digit()) {
+                    return Err(E::invalid_value(de::Unexpected::Str(s), &self));
+                }
+                s.parse()
+                    .map(Amount)
+                    .map_err(|_| E::invalid_value(de::Unexpected::Str(s), &self))
+            }
+        }
+
+        deserializer.deserialize_any(AmountVisitor)
+    }
+}
+
+/// Kinds of currency that can be earned and spent
+/// Adding a new one just needs a variant here, costs and rewards in JSON refer
+/// to it by its lowercase name
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Resource {
+    Cash,
+    Xp,
+    /// How happy customers are, earned by closing tickets quickly
+    /// It's only ever needed, never spent, so it's kept in `GameState` rather
+    /// than a wallet and prices can't include it (see `catalog::validate`)
+    Reputation,
+}
+
+impl Resource {
+    /// `n` of this resource, written short for the player, e.g. $1.2K or 40 XP
+    pub fn show(self, n: Amount) -> String {
+        match self {
+            Resource::Cash => format!("${}", n.short()),
+            Resource::Xp => format!("{} XP", n.short()),
+            Resource::Reputation => format!("{} reputation", n.short()),
+        }
+    }
+}
+
+/// An amount of each kind of resource, used for the wallet as well as prices
+/// Resources that aren't there count as 0. Zero amounts are never stored, so
+/// two of these are equal whenever they hold the same amounts
+/// In JSON it's an object like `{"cash": 10, "xp": 5}`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(
+    from = "BTreeMap<Resource, Amount>",
+    into = "BTreeMap<Resource, Amount>"
+)]
+pub struct Currency(BTreeMap<Resource, Amount>);
+
+impl From<BTreeMap<Resource, Amount>> for Currency {
+    fn from(mut amounts: BTreeMap<Resource, Amount>) -> Self {
+        amounts.retain(|_, n| *n > Amount::ZERO);
+        Self(amounts)
+    }
+}
+
+impl From<Currency> for BTreeMap<Resource, Amount> {
+    fn from(currency: Currency) -> Self {
+        currency.0
+    }
 }
 
 #[derive(Debug)]
 pub enum WalletError {
-    InsufficientCash,
-    InsufficientXP,
+    /// There wasn't enough of `kind`, it needed `short` more
+    Insufficient { kind: Resource, short: Amount },
 }
 
 impl Currency {
-    /// Start with 0 cash + 0 xp
+    /// Start with nothing of anything
     pub fn new() -> Self {
-        Self { cash: 0, xp: 0 }
+        Self(BTreeMap::new())
+    }
+
+    /// The same amounts, plus `n` of `kind`
+    pub fn with(mut self, kind: Resource, n: impl Into<Amount>) -> Self {
+        self.add(kind, n);
+        self
+    }
+
+    /// Returns the amount of one resource
+    pub fn get(&self, kind: Resource) -> Amount {
+        self.0.get(&kind).copied().unwrap_or_default()
+    }
+
+    /// Every resource there's some of, with how much
+    pub fn iter(&self) -> impl Iterator<Item = (Resource, Amount)> + '_ {
+        self.0.iter().map(|(kind, n)| (*kind, *n))
+    }
+
+    /// Add n of one resource in place
+    pub fn add(&mut self, kind: Resource, n: impl Into<Amount>) {
+        let total = self.get(kind) + n.into();
+        if total > Amount::ZERO {
+            self.0.insert(kind, total);
+        }
+    }
+
+    /// Add everything in `other` in place
+    pub fn add_all(&mut self, other: &Currency) {
+        for (kind, n) in other.iter() {
+            self.add(kind, n);
+        }
+    }
+
+    /// Take n of one resource away, or return how much more is needed
+    pub fn rm(&mut self, kind: Resource, n: impl Into<Amount>) -> Result<(), WalletError> {
+        let n = n.into();
+        let left = self
+            .get(kind)
+            .checked_sub(n)
+            .ok_or(WalletError::Insufficient {
+                kind,
+                short: n - self.get(kind),
+            })?;
+        if left > Amount::ZERO {
+            self.0.insert(kind, left);
+        } else {
+            self.0.remove(&kind);
+        }
+        Ok(())
     }
 
     /// Returns the amount of cash
-    pub fn cash(self) -> u64 {
-        self.cash
+    pub fn cash(&self) -> Amount {
+        self.get(Resource::Cash)
     }
 
     /// Returns the amount of xp
-    pub fn xp(self) -> u64 {
-        self.xp
+    pub fn xp(&self) -> Amount {
+        self.get(Resource::Xp)
     }
 
     /// Add n to cash in place
-    pub fn add_cash(&mut self, n: u64) {
-        self.cash += n;
+    pub fn add_cash(&mut self, n: impl Into<Amount>) {
+        self.add(Resource::Cash, n);
     }
 
     /// Add n to xp in place
-    pub fn add_xp(&mut self, n: u64) {
-        self.xp += n;
+    pub fn add_xp(&mut self, n: impl Into<Amount>) {
+        self.add(Resource::Xp, n);
     }
 
-    /// Return Some() when successful, return None when there isn't enough
-    pub fn rm_cash(&mut self, n: u64) -> Result<(), WalletError> {
-        if n > self.cash {
-            return Err(WalletError::InsufficientCash);
-        }
-        self.cash -= n;
-        Ok(())
+    /// Take n cash away, or return how much more is needed
+    pub fn rm_cash(&mut self, n: impl Into<Amount>) -> Result<(), WalletError> {
+        self.rm(Resource::Cash, n)
     }
 
-    /// Return Some() when successful, return None when there isn't enough
-    pub fn rm_xp(&mut self, n: u64) -> Result<(), WalletError> {
-        if n > self.xp {
-            return Err(WalletError::InsufficientXP);
-        }
-        self.xp -= n;
-        Ok(())
+    /// Take n xp away, or return how much more is needed
+    pub fn rm_xp(&mut self, n: impl Into<Amount>) -> Result<(), WalletError> {
+        self.rm(Resource::Xp, n)
+    }
+
+    /// Returns the first resource there isn't enough of to pay `price`, and
+    /// how much more is needed
+    pub fn missing(&self, price: &Currency) -> Option<(Resource, Amount)> {
+        price
+            .iter()
+            .find(|(kind, n)| *n > self.get(*kind))
+            .map(|(kind, n)| (kind, n - self.get(kind)))
     }
 
-    /// Atomically spend cash + XP
-    pub fn spend(&mut self, c: u64, x: u64) -> Result<(), WalletError> {
-        if c > self.cash {
-            return Err(WalletError::InsufficientCash);
+    /// Atomically spend everything in `price`
+    pub fn spend(&mut self, price: &Currency) -> Result<(), WalletError> {
+        if let Some((kind, short)) = self.missing(price) {
+            return Err(WalletError::Insufficient { kind, short });
         }
-        if x > self.xp {
-            return Err(WalletError::InsufficientXP);
+        for (kind, n) in price.iter() {
+            self.rm(kind, n).unwrap();
         }
-
-        self.rm_cash(c).unwrap();
-        self.rm_xp(x).unwrap();
         Ok(())
     }
+
+    /// Every amount written short, e.g. "$1.2K and 40 XP", or "" for nothing
+    pub fn short(&self) -> String {
+        self.iter()
+            .map(|(kind, n)| kind.show(n))
+            .collect::<Vec<_>>()
+            .join(" and ")
+    }
 }
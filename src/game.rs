@@ -1,13 +1,16 @@
+use crate::catalog;
+use crate::clock::*;
 use crate::currency::*;
+use crate::promotion::*;
+use crate::rng::*;
 use crate::ticket::*;
 use crate::upgrade::*;
 
 use rand::Rng;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::collections::HashSet;
-use std::fs::File;
-use std::io::BufReader;
-use std::io::prelude::*;
+use std::iter;
 
 #[test]
 fn empty() {
@@ -32,7 +35,9 @@ fn load_hashmap() {
             desc: "Gel wrist support: for when you're 25 but feel 65.".to_string(),
             cost: cost,
             requires: vec!["slightly_less_terrible_mouse".to_string()],
-            effects: vec![Effects::IncMultiplier(1.12)]
+            effects: vec![Effects::IncMultiplier(1.12)],
+            repeat: None,
+            reputation: None,
         })
         .as_ref()
     );
@@ -51,6 +56,35 @@ fn random_tickets() {
     }
 }
 
+#[test]
+fn same_seed_same_game() {
+    let mut a = GameState::with_seed(5);
+    let mut b = GameState::with_seed(5);
+    a.init_queue();
+    b.init_queue();
+    let clock = ManualClock::new(0);
+    for _ in 0..300 {
+        clock.advance(1);
+        a.tick(&clock);
+        b.tick(&clock);
+        a.click_ticket(0);
+        b.click_ticket(0);
+    }
+    assert!(a.tickets_closed() > 0);
+    assert_eq!(a.wallet, b.wallet);
+    assert_eq!(a.queue, b.queue);
+    assert_eq!(a.working, b.working);
+}
+
+#[test]
+fn different_seed_different_game() {
+    let mut a = GameState::with_seed(5);
+    let mut b = GameState::with_seed(6);
+    a.init_queue();
+    b.init_queue();
+    assert_ne!(a.working, b.working);
+}
+
 #[test]
 fn click_easy_complete() {
     let mut game = GameState::new();
@@ -78,12 +112,33 @@ fn click_easy_incomplete() {
 }
 
 #[test]
-#[ignore]
-fn click_multiplier_25() {
+fn save_requests() {
     let mut game = GameState::new();
+    assert!(!game.take_save_request());
+    game.working
+        .push(Ticket::new(Difficulty::Easy, Category::Web, "name"));
+    game.click_ticket(0);
+    assert!(!game.take_save_request());
+    for _ in 0..5 {
+        game.click_ticket(0);
+    }
+    assert!(game.take_save_request());
+    assert!(!game.take_save_request());
+
+    game.wallet.add_xp(30);
+    assert!(
+        game.buy_upgrade(&"wrist_stretch_reminder".to_string())
+            .is_ok()
+    );
+    assert!(game.take_save_request());
+}
+
+#[test]
+fn click_multiplier_25() {
+    let mut game = GameState::with_seed(42);
     game.working
         .push(Ticket::new(Difficulty::Hard, Category::Web, "name"));
-    game.multiplier += 0.25;
+    game.add_modifier(Effects::IncMultiplier(1.25));
     let clicks = Ticket::HARD_GOAL / 2; //needs to be small enough that it won't complete the ticket
     for _ in 0..clicks {
         game.click_ticket(0);
@@ -93,12 +148,11 @@ fn click_multiplier_25() {
 }
 
 #[test]
-#[ignore]
 fn click_multiplier_50() {
-    let mut game = GameState::new();
+    let mut game = GameState::with_seed(42);
     game.working
         .push(Ticket::new(Difficulty::Hard, Category::Web, "name"));
-    game.multiplier += 0.5;
+    game.add_modifier(Effects::IncMultiplier(1.5));
     let clicks = Ticket::HARD_GOAL / 2;
     for _ in 0..clicks {
         game.click_ticket(0);
@@ -107,12 +161,11 @@ fn click_multiplier_50() {
 }
 
 #[test]
-#[ignore]
 fn click_multiplier_75() {
-    let mut game = GameState::new();
+    let mut game = GameState::with_seed(42);
     game.working
         .push(Ticket::new(Difficulty::Hard, Category::Web, "name"));
-    game.multiplier += 0.75;
+    game.add_modifier(Effects::IncMultiplier(1.75));
     let clicks = Ticket::HARD_GOAL / 2;
     for _ in 0..clicks {
         game.click_ticket(0);
@@ -149,86 +202,891 @@ fn upgrade_insuff_cash() {
     assert!(
         game.buy_upgrade(&"slightly_less_terrible_mouse".to_string())
             .is_err_and(|x| match x {
-                BuyError::Wallet(WalletError::InsufficientCash) => true,
+                BuyError::Wallet(WalletError::Insufficient { kind, short }) => {
+                    kind == Resource::Cash && short == 40
+                }
                 _ => false,
             })
     );
 }
 
 #[test]
-fn upgrade_insuff_xp() {
-    let mut game = GameState::new();
-    assert!(
-        game.buy_upgrade(&"wrist_stretch_reminder".to_string())
-            .is_err_and(|x| match x {
-                BuyError::Wallet(WalletError::InsufficientXP) => true,
-                _ => false,
-            })
-    );
+fn upgrade_insuff_xp() {
+    let mut game = GameState::new();
+    assert!(
+        game.buy_upgrade(&"wrist_stretch_reminder".to_string())
+            .is_err_and(|x| match x {
+                BuyError::Wallet(WalletError::Insufficient { kind, short }) => {
+                    kind == Resource::Xp && short == 30
+                }
+                _ => false,
+            })
+    );
+}
+
+#[cfg(test)]
+fn with_repeatable(max: Option<u32>) -> GameState {
+    let mut game = GameState::with_seed(1);
+    let mut cost = Currency::new();
+    cost.add_cash(100);
+    let mut step = Currency::new();
+    step.add_cash(50);
+    let id = "coffee".to_string();
+    game.upgrades.insert(
+        id.clone(),
+        Upgrade {
+            id,
+            name: "Coffee".to_string(),
+            desc: String::new(),
+            cost,
+            requires: Vec::new(),
+            effects: vec![Effects::IncMultiplier(1.5)],
+            repeat: Some(Repeat {
+                max,
+                cost: CostCurve::Linear(step),
+            }),
+            reputation: None,
+        },
+    );
+    game
+}
+
+#[test]
+fn upgrade_repeat() {
+    let mut game = with_repeatable(None);
+    let id = "coffee".to_string();
+    game.wallet.add_cash(100 + 150 + 200);
+    for _ in 0..3 {
+        assert!(game.buy_upgrade(&id).is_ok());
+    }
+    assert_eq!(game.wallet.cash(), 0);
+    assert_eq!(game.level(&id), 3);
+    assert_eq!(game.price(&game.upgrades[&id]).cash(), 250);
+    assert!(game.avail_upgrades().iter().any(|x| x.id == id));
+    // effects stack once per level
+    assert!((game.stats().multiplier - 1.5f32.powi(3)).abs() < 0.0001);
+}
+
+#[test]
+fn upgrade_repeat_max() {
+    let mut game = with_repeatable(Some(2));
+    let id = "coffee".to_string();
+    game.wallet.add_cash(1000);
+    assert!(game.buy_upgrade(&id).is_ok());
+    assert!(game.buy_upgrade(&id).is_ok());
+    assert!(
+        game.buy_upgrade(&id)
+            .is_err_and(|x| matches!(x, BuyError::UpgradeUnavailable))
+    );
+    assert_eq!(game.level(&id), 2);
+    assert!(!game.avail_upgrades().iter().any(|x| x.id == id));
+}
+
+#[test]
+fn upgrade_once() {
+    let mut game = GameState::new();
+    let id = "wrist_stretch_reminder".to_string();
+    game.wallet.add_xp(60);
+    assert!(game.buy_upgrade(&id).is_ok());
+    assert_eq!(game.level(&id), 1);
+    assert!(game.buy_upgrade(&id).is_err());
+}
+
+#[test]
+fn promote_too_early() {
+    let mut game = GameState::with_seed(1);
+    assert!(matches!(game.promote(), Err(PromotionError::NothingEarned)));
+}
+
+#[test]
+fn promote_resets_run() {
+    let mut game = GameState::with_seed(1);
+    game.init_queue();
+    game.set_rounding(Rounding::Carry);
+    game.career.earn(Career::PROMOTION_CASH * 4);
+    game.wallet.add_xp(30);
+    assert!(
+        game.buy_upgrade(&"wrist_stretch_reminder".to_string())
+            .is_ok()
+    );
+    game.add_modifier(Effects::IncCashMultiplier(2.0));
+    game.step(10);
+
+    assert_eq!(game.promote().unwrap(), 2);
+    assert_eq!(game.wallet, Currency::new());
+    assert!(game.purchased.is_empty());
+    assert!(game.modifiers.is_empty());
+    assert_eq!(game.stats(), Stats::default());
+    assert_eq!(game.working.len(), 4);
+    assert_eq!(game.rounding(), Rounding::Carry);
+    assert_eq!(game.last_tick, Some(10));
+    assert_eq!(game.career.seniority(), 2);
+    assert!(game.take_save_request());
+}
+
+#[test]
+fn reputation_carries_over() {
+    let mut game = GameState::with_seed(1);
+    game.career.earn(Career::PROMOTION_CASH);
+    game.reputation = Amount::from(40);
+    game.promote().unwrap();
+    assert_eq!(game.reputation(), 40);
+}
+
+#[test]
+fn arrival_rate_carries_over() {
+    let mut game = GameState::with_seed(1);
+    game.career.earn(Career::PROMOTION_CASH);
+    game.set_arrival_rate(0.5);
+    game.promote().unwrap();
+    assert_eq!(game.arrival_rate(), 0.5);
+}
+
+#[test]
+fn perks_carry_over() {
+    let mut game = GameState::with_seed(1);
+    game.career.earn(Career::PROMOTION_CASH);
+    game.promote().unwrap();
+    assert!(game.buy_perk("mentor").is_ok());
+    assert!((game.stats().multiplier - 1.25).abs() < 0.0001);
+    game.career.earn(Career::PROMOTION_CASH * 3);
+    game.promote().unwrap();
+    assert!((game.stats().multiplier - 1.25).abs() < 0.0001);
+}
+
+#[test]
+fn closing_tickets_counts_towards_promotion() {
+    let mut game = GameState::new();
+    game.working
+        .push(Ticket::new(Difficulty::Easy, Category::Web, "name"));
+    for _ in 0..=Ticket::EASY_GOAL {
+        game.click_ticket(0);
+    }
+    assert_eq!(game.career.lifetime_cash(), GameState::BASE_EASY_CASH);
+}
+
+#[test]
+fn autosolve_none() {
+    let mut game = GameState::new();
+    game.init_queue();
+    for _ in 0..30 {
+        game.autosolve();
+    }
+    for ticket in game.working {
+        assert_eq!(ticket.clicked(), 0);
+    }
+}
+
+#[test]
+fn autosolve_one() {
+    let mut game = GameState::new();
+    game.init_queue();
+    let diff = game.working[0].difficulty();
+    let cat = game.working[0].category();
+    game.add_modifier(Effects::AutoSolve(*diff, *cat));
+    for _ in 0..4 {
+        game.autosolve();
+    }
+    assert_eq!(game.working[0].clicked(), 4);
+}
+
+#[test]
+fn autosolve_dup() {
+    let mut game = GameState::new();
+    game.init_queue();
+    game.working.push(game.working[0].clone());
+    let diff = game.working[0].difficulty();
+    let cat = game.working[0].category();
+    game.add_modifier(Effects::AutoSolve(*diff, *cat));
+    for _ in 0..4 {
+        game.autosolve();
+    }
+    assert_eq!(game.working[0].clicked(), 4);
+    assert_eq!(game.working[4].clicked(), 4);
+}
+
+#[test]
+fn autosolve_two() {
+    let mut game = GameState::new();
+    game.init_queue();
+    let diff = *game.working[0].difficulty();
+    let cat = *game.working[0].category();
+    let diff1 = *game.working[1].difficulty();
+    let cat1 = *game.working[1].category();
+    game.add_modifier(Effects::AutoSolve(diff, cat));
+    game.add_modifier(Effects::AutoSolve(diff1, cat1));
+    for _ in 0..4 {
+        game.autosolve();
+    }
+    assert_eq!(game.working[0].clicked(), 4);
+    assert_eq!(game.working[1].clicked(), 4);
+}
+
+#[test]
+fn autosolve_no_autoclose() {
+    let mut game = GameState::new();
+    game.working
+        .push(Ticket::new(Difficulty::Easy, Category::Web, "name"));
+    game.add_modifier(Effects::AutoSolve(Difficulty::Easy, Category::Web));
+    for _ in 0..10 {
+        game.autosolve();
+    }
+    assert_eq!(game.working.len(), 1);
+    assert!(game.working[0].is_complete());
+    assert_eq!(game.wallet.cash(), 0);
+}
+
+#[test]
+fn autosolve_autoclose() {
+    let mut game = GameState::new();
+    game.working
+        .push(Ticket::new(Difficulty::Easy, Category::Web, "name"));
+    game.working
+        .push(Ticket::new(Difficulty::Hard, Category::Misc, "name"));
+    game.working
+        .push(Ticket::new(Difficulty::Easy, Category::Web, "name1"));
+    game.add_modifier(Effects::AutoSolve(Difficulty::Easy, Category::Web));
+    game.add_modifier(Effects::AutoClose);
+    for _ in 0..Ticket::EASY_GOAL + 1 {
+        game.autosolve();
+    }
+    assert_eq!(game.working.len(), 1);
+    assert_eq!(game.working[0].difficulty(), &Difficulty::Hard);
+    assert_eq!(game.wallet.cash(), GameState::BASE_EASY_CASH * 2);
+    assert_eq!(game.wallet.xp(), GameState::BASE_EASY_XP * 2);
+    assert_eq!(game.tickets_closed(), 2);
+}
+
+#[test]
+fn autoclose_leaves_manual_tickets() {
+    let mut game = GameState::new();
+    game.working
+        .push(Ticket::new(Difficulty::Easy, Category::Web, "name"));
+    for _ in 0..Ticket::EASY_GOAL {
+        game.click_ticket(0);
+    }
+    game.working[0].click(1);
+    game.add_modifier(Effects::AutoClose);
+    game.autosolve();
+    assert_eq!(game.working.len(), 1);
+}
+
+#[test]
+fn catch_up_autoclose() {
+    let mut game = GameState::new();
+    for _ in 0..3 {
+        game.working
+            .push(Ticket::new(Difficulty::Easy, Category::Misc, "name"));
+    }
+    game.add_modifier(Effects::AutoSolve(Difficulty::Easy, Category::Misc));
+    game.add_modifier(Effects::AutoClose);
+    let clock = ManualClock::new(0);
+    game.tick(&clock);
+    // short enough that no ticket arriving in the meantime can be finished
+    clock.advance(Ticket::EASY_GOAL as i64);
+    let offline = game.catch_up(&clock, 3600).unwrap();
+    assert_eq!(offline.tickets, 3);
+    assert_eq!(offline.cash, GameState::BASE_EASY_CASH * 3);
+    assert_eq!(offline.xp, GameState::BASE_EASY_XP * 3);
+}
+
+#[test]
+fn queue_fifo() {
+    let mut game = GameState::new();
+    game.queue
+        .push(Ticket::new(Difficulty::Easy, Category::Web, "first"));
+    game.queue
+        .push(Ticket::new(Difficulty::Easy, Category::Web, "second"));
+    game.assign_next_ticket();
+    assert_eq!(game.working[0].name(), "first");
+    assert_eq!(game.backlog(), 1);
+}
+
+#[test]
+fn assign_by_priority() {
+    let mut game = GameState::new();
+    for (name, priority) in [
+        ("low", Priority::P4),
+        ("normal", Priority::P3),
+        ("urgent", Priority::P1),
+        ("urgent later", Priority::P1),
+    ] {
+        let mut ticket = Ticket::new(Difficulty::Easy, Category::Web, name);
+        ticket.set_priority(priority);
+        game.queue.push(ticket);
+    }
+    for _ in 0..4 {
+        game.assign_next_ticket();
+    }
+    let names: Vec<_> = game.working.iter().map(|t| t.name().as_str()).collect();
+    assert_eq!(names, vec!["urgent", "urgent later", "normal", "low"]);
+}
+
+#[test]
+fn priority_payout() {
+    for (priority, payout) in [
+        (Priority::P1, GameState::P1_PAYOUT),
+        (Priority::P2, GameState::P2_PAYOUT),
+        (Priority::P3, GameState::P3_PAYOUT),
+        (Priority::P4, GameState::P4_PAYOUT),
+    ] {
+        let mut game = GameState::new();
+        let mut ticket = Ticket::new(Difficulty::Hard, Category::Web, "name");
+        ticket.set_priority(priority);
+        game.working.push(ticket);
+        for _ in 0..Ticket::HARD_GOAL + 1 {
+            game.click_ticket(0);
+        }
+        // all of these payouts come out to whole numbers
+        assert_eq!(
+            game.wallet.cash(),
+            (GameState::BASE_HARD_CASH as f32 * payout) as u64
+        );
+        assert_eq!(
+            game.wallet.xp(),
+            (GameState::BASE_HARD_XP as f32 * payout) as u64
+        );
+    }
+}
+
+#[test]
+fn priority_upgrade() {
+    let mut game = GameState::new();
+    game.add_modifier(Effects::IncPriorityMultiplier(Priority::P1, 1.5));
+    game.add_modifier(Effects::IncPriorityMultiplier(Priority::P1, 2.0));
+    assert_eq!(game.stats().priority_mult.get(&Priority::P1), Some(&3.0));
+    assert_eq!(game.stats().priority_mult.get(&Priority::P2), None);
+
+    let mut ticket = Ticket::new(Difficulty::Easy, Category::Web, "name");
+    ticket.set_priority(Priority::P1);
+    game.working.push(ticket);
+    game.working
+        .push(Ticket::new(Difficulty::Easy, Category::Web, "name"));
+    for _ in 0..(Ticket::EASY_GOAL + 1) * 2 {
+        game.click_ticket(0);
+    }
+    let p1 = (GameState::BASE_EASY_CASH as f32 * GameState::P1_PAYOUT * 3.0) as u64;
+    assert_eq!(game.wallet.cash(), p1 + GameState::BASE_EASY_CASH);
+}
+
+#[test]
+fn fill_to_capacity() {
+    let mut game = GameState::new();
+    for _ in 0..10 {
+        game.spawn_ticket();
+    }
+    game.fill_working();
+    assert_eq!(game.working.len(), GameState::WORKING_CAPACITY);
+    assert_eq!(game.backlog(), 10 - GameState::WORKING_CAPACITY);
+}
+
+#[test]
+fn close_pulls_from_queue() {
+    let mut game = GameState::new();
+    game.working
+        .push(Ticket::new(Difficulty::Easy, Category::Web, "name"));
+    game.queue
+        .push(Ticket::new(Difficulty::Hard, Category::Misc, "next"));
+    for _ in 0..Ticket::EASY_GOAL + 1 {
+        game.click_ticket(0);
+    }
+    assert_eq!(game.working.len(), 1);
+    assert_eq!(game.working[0].name(), "next");
+    assert_eq!(game.backlog(), 0);
+}
+
+#[test]
+fn queue_capped() {
+    let mut game = GameState::new();
+    for _ in 0..(GameState::MAX_QUEUE as f64 * 100.0 / GameState::ARRIVAL_RATE) as usize {
+        game.ticket_arrivals();
+    }
+    assert_eq!(game.backlog(), GameState::MAX_QUEUE);
+}
+
+#[test]
+fn arrival_rate_setting() {
+    let mut game = GameState::with_seed(3);
+    assert_eq!(game.arrival_rate(), GameState::ARRIVAL_RATE);
+    game.set_arrival_rate(0.0);
+    for _ in 0..100 {
+        game.ticket_arrivals();
+    }
+    assert_eq!(game.backlog(), 0);
+    game.set_arrival_rate(-1.0);
+    assert_eq!(game.arrival_rate(), 0.0);
+    game.set_arrival_rate(f64::NAN);
+    assert_eq!(game.arrival_rate(), 0.0);
+    game.set_arrival_rate(2.0);
+    for _ in 0..5 {
+        game.ticket_arrivals();
+    }
+    assert!(game.backlog() > 3);
+}
+
+#[test]
+fn arrivals_fill_board() {
+    let mut game = GameState::new();
+    let clock = ManualClock::new(0);
+    game.tick(&clock);
+    clock.advance(3600);
+    let offline = game.catch_up(&clock, 3600).unwrap();
+    assert_eq!(offline.secs, 3600);
+    assert_eq!(game.working.len(), GameState::WORKING_CAPACITY);
+    assert!(game.backlog() > 0);
+}
+
+#[test]
+fn poisson_mean() {
+    let mut rng = GameRng::new(0);
+    for rate in [0.2, 1.0, 3.5] {
+        let n = 100_000;
+        let total: u32 = (0..n).map(|_| poisson(&mut rng, rate)).sum();
+        let mean = total as f64 / n as f64;
+        // standard error is sqrt(rate / n), this is more than 10 of them
+        assert!(
+            (mean - rate).abs() < 0.05,
+            "rate {} gave mean {}",
+            rate,
+            mean
+        );
+    }
+}
+
+#[test]
+fn rand_round_mean() {
+    let mut rng = GameRng::new(0);
+    for base in [1, 12, 60] {
+        for mult in [1.1, 1.3, 1.6, 2.4, 3.7] {
+            let n = 100_000;
+            let total: u64 = (0..n).map(|_| rand_round(&mut rng, base, mult)).sum();
+            let mean = total as f64 / n as f64;
+            let exact = base as f64 * mult as f64;
+            // standard error is at most 0.5 / sqrt(n), this is more than 6 of them
+            assert!(
+                (mean - exact).abs() < 0.01,
+                "{} * {} gave mean {}",
+                base,
+                mult,
+                mean
+            );
+        }
+    }
+}
+
+#[test]
+fn rand_round_neighbours() {
+    let mut rng = GameRng::new(0);
+    for _ in 0..1000 {
+        let x = rand_round(&mut rng, 12, 1.3);
+        assert!(x == 15 || x == 16);
+    }
+    // whole results never get rounded
+    assert!((0..1000).all(|_| rand_round(&mut rng, 12, 1.5) == 18));
+}
+
+#[test]
+fn carry_round_total() {
+    for base in [1, 12, 60] {
+        for mult in [1.1, 1.3, 1.6, 2.4, 3.7] {
+            let mut carry = 0.0;
+            let n = 10_000;
+            let total: u64 = (0..n).map(|_| carry_round(&mut carry, base, mult)).sum();
+            let exact = n as f64 * base as f64 * mult as f64;
+            assert!((total as f64 - exact).abs() <= 1.0);
+            assert!((0.0..1.0).contains(&carry));
+        }
+    }
+}
+
+#[test]
+fn carry_clicks() {
+    let mut game = GameState::new();
+    game.set_rounding(Rounding::Carry);
+    game.working
+        .push(Ticket::new(Difficulty::Hard, Category::Web, "name"));
+    game.add_modifier(Effects::IncMultiplier(1.5));
+    for _ in 0..10 {
+        game.click_ticket(0);
+    }
+    assert_eq!(game.working[0].clicked(), 15);
+}
+
+#[test]
+fn carry_payouts() {
+    let mut game = GameState::new();
+    game.set_rounding(Rounding::Carry);
+    game.add_modifier(Effects::IncCashMultiplier(1.25));
+    for _ in 0..4 {
+        game.working
+            .push(Ticket::new(Difficulty::Easy, Category::Web, "name"));
+    }
+    for _ in 0..4 {
+        for _ in 0..=game.working[0].goal() {
+            game.click_ticket(0);
+        }
+    }
+    assert_eq!(game.wallet.cash(), GameState::BASE_EASY_CASH * 5);
+    assert_eq!(game.wallet.xp(), GameState::BASE_EASY_XP * 4);
+}
+
+#[test]
+fn spawn_opens_ticket() {
+    let mut game = GameState::new();
+    game.spawn_ticket();
+    assert_eq!(game.queue[0].opened(), None);
+    game.tick(&ManualClock::new(500));
+    game.spawn_ticket();
+    assert_eq!(game.queue.last().unwrap().opened(), Some(500));
+    // the first one gets its SLA started by the tick
+    assert_eq!(game.working[0].opened(), Some(500));
+}
+
+#[test]
+fn close_within_sla() {
+    let mut game = GameState::new();
+    let mut ticket = Ticket::new(Difficulty::Easy, Category::Web, "name");
+    ticket.open(0);
+    game.working.push(ticket);
+    game.last_tick = Some(Ticket::EASY_SLA);
+    for _ in 0..Ticket::EASY_GOAL + 1 {
+        game.click_ticket(0);
+    }
+    assert_eq!(game.wallet.cash(), GameState::BASE_EASY_CASH);
+    assert_eq!(game.wallet.xp(), GameState::BASE_EASY_XP);
+    assert_eq!(game.tickets_breached(), 0);
+}
+
+#[test]
+fn close_breached() {
+    let mut game = GameState::new();
+    // half of a medium ticket's cash is 12.5, carrying keeps the result exact
+    game.set_rounding(Rounding::Carry);
+    let mut ticket = Ticket::new(Difficulty::Med, Category::Web, "name");
+    ticket.open(0);
+    game.working.push(ticket);
+    game.last_tick = Some(Ticket::MED_SLA + 1);
+    for _ in 0..Ticket::MED_GOAL + 1 {
+        game.click_ticket(0);
+    }
+    assert_eq!(
+        game.wallet.cash(),
+        (GameState::BASE_MED_CASH as f32 * GameState::BREACH_PAYOUT) as u64
+    );
+    assert_eq!(
+        game.wallet.xp(),
+        (GameState::BASE_MED_XP as f32 * GameState::BREACH_PAYOUT) as u64
+    );
+    assert_eq!(game.tickets_closed(), 1);
+    assert_eq!(game.tickets_breached(), 1);
+}
+
+#[test]
+fn count_closed() {
+    let mut game = GameState::new();
+    for _ in 0..2 {
+        game.working
+            .push(Ticket::new(Difficulty::Easy, Category::Web, "name"));
+    }
+    for _ in 0..12 {
+        game.click_ticket(0);
+    }
+    assert_eq!(game.tickets_closed(), 2);
+}
+
+/// Click an easy ticket opened at 0 closed at `now`
+#[cfg(test)]
+fn close_easy_at(game: &mut GameState, now: i64) {
+    let mut ticket = Ticket::new(Difficulty::Easy, Category::Web, "name");
+    ticket.open(0);
+    game.working.push(ticket);
+    game.last_tick = Some(now);
+    let index = game.working.len() - 1;
+    for _ in 0..Ticket::EASY_GOAL + 1 {
+        game.click_ticket(index);
+    }
+}
+
+#[test]
+fn reputation_for_speed() {
+    let mut game = GameState::new();
+    close_easy_at(&mut game, Ticket::EASY_SLA / 2);
+    assert_eq!(game.reputation(), GameState::FAST_REPUTATION);
+    close_easy_at(&mut game, Ticket::EASY_SLA);
+    assert_eq!(
+        game.reputation(),
+        GameState::FAST_REPUTATION + GameState::CLOSE_REPUTATION
+    );
+    // a breach costs more than the rest earned, but it stops at 0
+    close_easy_at(&mut game, Ticket::EASY_SLA + 1);
+    assert_eq!(game.reputation(), 0);
+}
+
+#[test]
+fn reputation_capped() {
+    let mut game = GameState::new();
+    game.reputation = Amount::from(GameState::MAX_REPUTATION - 1);
+    close_easy_at(&mut game, 0);
+    assert_eq!(game.reputation(), GameState::MAX_REPUTATION);
+}
+
+#[test]
+fn languishing_costs_reputation() {
+    let mut game = GameState::new();
+    game.reputation = Amount::from(10);
+    for opened in [0, 0, 100] {
+        let mut ticket = Ticket::new(Difficulty::Easy, Category::Web, "name");
+        ticket.open(opened);
+        game.working.push(ticket);
+    }
+    game.last_tick = Some(Ticket::EASY_SLA);
+    // only charged once a minute from the first tick, and only for the two
+    // that are late by then
+    game.step(60);
+    assert_eq!(game.reputation(), 10);
+    game.step(1);
+    assert_eq!(game.reputation(), 10 - 2 * GameState::LANGUISH_REPUTATION);
+}
+
+#[test]
+fn languishing_between_ticks() {
+    let mut game = GameState::new();
+    game.reputation = Amount::from(10);
+    let mut ticket = Ticket::new(Difficulty::Easy, Category::Web, "name");
+    ticket.open(0);
+    game.working.push(ticket);
+    game.last_tick = Some(Ticket::EASY_SLA + 1);
+    game.languish();
+    // the GUI ticks a little more than a second apart, so most minutes don't
+    // land on a tick
+    game.last_tick = Some(Ticket::EASY_SLA + 62);
+    game.languish();
+    assert_eq!(game.reputation(), 10 - GameState::LANGUISH_REPUTATION);
+    game.last_tick = Some(Ticket::EASY_SLA + 183);
+    game.languish();
+    assert_eq!(game.reputation(), 10 - 3 * GameState::LANGUISH_REPUTATION);
+}
+
+#[test]
+fn reputation_shifts_difficulty() {
+    assert_eq!(
+        GameState::difficulty_odds(Amount::ZERO),
+        GameState::DIFFICULTY_ODDS
+    );
+    let odds = GameState::difficulty_odds(Amount::from(GameState::REPUTATION_PER_STEP * 2));
+    assert_eq!(odds[0], (Difficulty::Easy, 2));
+    assert_eq!(odds[2], (Difficulty::Hard, 5));
+    // there are always some easy tickets
+    let odds = GameState::difficulty_odds(Amount::from(GameState::MAX_REPUTATION));
+    assert_eq!(odds[0], (Difficulty::Easy, 1));
+    assert_eq!(odds.iter().map(|(_, x)| x).sum::<u32>(), 10);
+}
+
+#[test]
+fn upgrade_needs_reputation() {
+    let mut game = with_repeatable(Some(1));
+    let id = "coffee".to_string();
+    game.upgrades.get_mut(&id).unwrap().reputation = Some(20);
+    game.wallet.add_cash(1000);
+    assert!(!game.avail_upgrades().iter().any(|x| x.id == id));
+    assert!(game.buy_upgrade(&id).is_err());
+    game.reputation = Amount::from(20);
+    assert!(game.avail_upgrades().iter().any(|x| x.id == id));
+    assert!(game.buy_upgrade(&id).is_ok());
+    // reputation is only needed, not spent
+    assert_eq!(game.reputation(), 20);
+}
+
+#[test]
+fn reload_same_catalog() {
+    let mut game = GameState::new();
+    game.wallet.add_cash(1000);
+    game.wallet.add_xp(1000);
+    for id in [
+        "wrist_stretch_reminder",
+        "slightly_less_terrible_mouse",
+        "ergonomic_mousepad",
+        "better_chair_cushion",
+        "extra_loud_mech_keyboard",
+        "autosolve_easy_misc",
+        "pager_duty_rotation",
+    ] {
+        assert!(game.buy_upgrade(&id.to_string()).is_ok());
+    }
+    let stats = game.stats();
+    game.reload_upgrades(catalog::embedded());
+    assert_eq!(game.stats(), stats);
+}
+
+#[test]
+fn reload_new_numbers() {
+    let mut game = GameState::new();
+    game.wallet.add_xp(30);
+    assert!(
+        game.buy_upgrade(&"wrist_stretch_reminder".to_string())
+            .is_ok()
+    );
+    let mut upgrades = catalog::embedded();
+    for upgrade in upgrades.iter_mut() {
+        if upgrade.id == "wrist_stretch_reminder" {
+            upgrade.effects = vec![Effects::IncMultiplier(2.0), Effects::AutoClose];
+        }
+    }
+    game.reload_upgrades(upgrades);
+    assert_eq!(game.stats().multiplier, 2.0);
+    assert!(game.stats().autoclose);
+    assert_eq!(game.wallet.xp(), 0);
+}
+
+#[test]
+fn reload_without_purchase() {
+    let mut game = GameState::new();
+    game.wallet.add_xp(30);
+    let id = "wrist_stretch_reminder".to_string();
+    assert!(game.buy_upgrade(&id).is_ok());
+    let upgrades = catalog::embedded()
+        .into_iter()
+        .filter(|x| x.id != id)
+        .collect();
+    game.reload_upgrades(upgrades);
+    assert_eq!(game.stats().multiplier, 1.0);
+    assert!(game.purchased.contains(&id));
+    assert!(!game.upgrades.contains_key(&id));
+}
+
+#[test]
+fn stats_from_purchases() {
+    let mut game = GameState::new();
+    assert_eq!(game.stats(), Stats::default());
+    game.wallet.add_cash(1000);
+    game.wallet.add_xp(1000);
+    for id in ["wrist_stretch_reminder", "slightly_less_terrible_mouse"] {
+        assert!(game.buy_upgrade(&id.to_string()).is_ok());
+    }
+    assert!((game.stats().multiplier - 1.08 * 1.06).abs() < 0.0001);
+}
+
+#[test]
+fn stats_from_effects() {
+    let stats = Stats::from_effects(&[
+        Effects::IncMultiplier(1.5),
+        Effects::IncMultiplier(2.0),
+        Effects::IncCashMultiplier(1.25),
+        Effects::AutoSolve(Difficulty::Easy, Category::Web),
+        Effects::AutoSolve(Difficulty::Easy, Category::Web),
+        Effects::AutoClose,
+    ]);
+    assert_eq!(stats.multiplier, 3.0);
+    assert_eq!(stats.cash_mult, 1.25);
+    assert_eq!(stats.xp_mult, 1.0);
+    assert_eq!(stats.autosolve.len(), 1);
+    assert!(stats.autoclose);
+}
+
+#[test]
+fn modifiers() {
+    let mut game = GameState::new();
+    game.add_modifier(Effects::IncXPMultiplier(2.0));
+    game.add_modifier(Effects::IncXPMultiplier(1.5));
+    assert_eq!(game.stats().xp_mult, 3.0);
+    game.clear_modifiers();
+    assert_eq!(game.stats(), Stats::default());
+}
+
+#[test]
+fn step_time() {
+    let mut game = GameState::with_seed(3);
+    game.step(10);
+    assert_eq!(game.last_tick, Some(10));
+    game.step(5);
+    assert_eq!(game.last_tick, Some(15));
+    game.step(0);
+    assert_eq!(game.last_tick, Some(15));
+}
+
+#[test]
+fn catch_up_keeps_reputation() {
+    let mut game = GameState::with_seed(5);
+    game.reputation = Amount::from(100);
+    game.set_arrival_rate(0.0);
+    let mut ticket = Ticket::new(Difficulty::Hard, Category::Web, "late");
+    ticket.open(0);
+    game.working.push(ticket);
+    let clock = ManualClock::new(Ticket::HARD_SLA + 1);
+    game.tick(&clock);
+    clock.advance(3600);
+    game.catch_up(&clock, 3600).unwrap();
+    assert_eq!(game.reputation(), 100);
+    // back online, only minutes from now on are charged
+    game.step(60);
+    assert_eq!(game.reputation(), 100 - GameState::LANGUISH_REPUTATION);
 }
 
 #[test]
-fn autosolve_none() {
+fn step_matches_catch_up() {
+    let mut stepped = GameState::with_seed(8);
+    let mut caught_up = GameState::with_seed(8);
+    let clock = ManualClock::new(100);
+    stepped.tick(&clock);
+    caught_up.tick(&clock);
+    clock.advance(600);
+    stepped.step(600);
+    caught_up.catch_up(&clock, 600);
+    assert_eq!(stepped.last_tick, caught_up.last_tick);
+    assert_eq!(stepped.queue, caught_up.queue);
+    assert_eq!(stepped.working, caught_up.working);
+}
+
+#[test]
+fn catch_up_never_ticked() {
     let mut game = GameState::new();
-    game.init_queue();
-    for _ in 0..30 {
-        game.autosolve();
-    }
-    for ticket in game.working {
-        assert_eq!(ticket.clicked(), 0);
-    }
+    assert_eq!(game.catch_up(&ManualClock::new(1000), 100), None);
 }
 
 #[test]
-fn autosolve_one() {
+fn catch_up_no_time() {
     let mut game = GameState::new();
-    game.init_queue();
-    let diff = game.working[0].difficulty();
-    let cat = game.working[0].category();
-    let _ = game.autosolve.insert((*diff, *cat));
-    for _ in 0..4 {
-        game.autosolve();
-    }
-    assert_eq!(game.working[0].clicked(), 4);
+    let clock = ManualClock::new(1000);
+    game.tick(&clock);
+    assert_eq!(game.catch_up(&clock, 100), None);
+    // clock went backwards
+    clock.advance(-100);
+    assert_eq!(game.catch_up(&clock, 100), None);
 }
 
 #[test]
-fn autosolve_dup() {
+fn catch_up_autosolve() {
     let mut game = GameState::new();
-    game.init_queue();
-    game.working.push(game.working[0].clone());
-    let diff = game.working[0].difficulty();
-    let cat = game.working[0].category();
-    let _ = game.autosolve.insert((*diff, *cat));
-    for _ in 0..4 {
-        game.autosolve();
-    }
-    assert_eq!(game.working[0].clicked(), 4);
-    assert_eq!(game.working[4].clicked(), 4);
+    game.working
+        .push(Ticket::new(Difficulty::Hard, Category::Web, "name"));
+    game.add_modifier(Effects::AutoSolve(Difficulty::Hard, Category::Web));
+    let clock = ManualClock::new(1000);
+    game.tick(&clock);
+    clock.advance(10);
+    let offline = game.catch_up(&clock, 100).unwrap();
+    assert_eq!(offline.secs, 10);
+    assert_eq!(game.working[0].clicked(), 11);
+    assert_eq!(game.last_tick, Some(1010));
 }
 
 #[test]
-fn autosolve_two() {
+fn catch_up_capped() {
     let mut game = GameState::new();
-    game.init_queue();
-    let diff = game.working[0].difficulty();
-    let cat = game.working[0].category();
-    let diff1 = game.working[1].difficulty();
-    let cat1 = game.working[1].category();
-    let _ = game.autosolve.insert((*diff, *cat));
-    _ = game.autosolve.insert((*diff1, *cat1));
-    for _ in 0..4 {
-        game.autosolve();
-    }
-    assert_eq!(game.working[0].clicked(), 4);
-    assert_eq!(game.working[1].clicked(), 4);
+    game.working
+        .push(Ticket::new(Difficulty::Hard, Category::Web, "name"));
+    game.add_modifier(Effects::AutoSolve(Difficulty::Hard, Category::Web));
+    let clock = ManualClock::new(0);
+    game.tick(&clock);
+    clock.advance(1_000_000);
+    let offline = game.catch_up(&clock, 20).unwrap();
+    assert_eq!(offline.secs, 20);
+    assert_eq!(game.working[0].clicked(), 21);
 }
 
 #[test]
-#[ignore]
 fn cash_multiplier_25() {
-    let mut game = GameState::new();
-    game.cash_mult += 0.25;
+    let mut game = GameState::with_seed(42);
+    game.add_modifier(Effects::IncCashMultiplier(1.25));
     for _ in 0..8 {
         game.working
             .push(Ticket::new(Difficulty::Easy, Category::Web, "name"));
@@ -245,10 +1103,9 @@ fn cash_multiplier_25() {
 }
 
 #[test]
-#[ignore]
 fn xp_multiplier_25() {
-    let mut game = GameState::new();
-    game.xp_mult += 0.25;
+    let mut game = GameState::with_seed(42);
+    game.add_modifier(Effects::IncXPMultiplier(1.25));
     for _ in 0..8 {
         game.working
             .push(Ticket::new(Difficulty::Easy, Category::Web, "name"));
@@ -264,6 +1121,81 @@ fn xp_multiplier_25() {
     assert!(game.wallet.xp() < (GameState::BASE_EASY_XP as f32 * 1.25).ceil() as u64 * 8)
 }
 
+#[test]
+fn preview_upgrade() {
+    let mut game = GameState::with_seed(1);
+    game.add_modifier(Effects::IncMultiplier(2.0));
+    let id = "wrist_stretch_reminder";
+    let preview = game.preview(id).unwrap();
+    assert_eq!(preview.before, game.stats());
+    let mut after = game.stats();
+    for effect in &game.upgrades[id].effects {
+        after.apply(effect);
+    }
+    assert_eq!(preview.after, after);
+    // nothing was actually bought
+    assert!(game.purchased.is_empty());
+    assert!(game.preview("not_an_upgrade").is_none());
+}
+
+#[test]
+fn preview_new_autosolve() {
+    let mut game = GameState::with_seed(1);
+    game.add_modifier(Effects::AutoSolve(Difficulty::Easy, Category::Web));
+    let id = "first_upgrade";
+    game.upgrades.insert(
+        id.to_string(),
+        Upgrade {
+            id: id.to_string(),
+            name: String::new(),
+            desc: String::new(),
+            cost: Currency::new(),
+            requires: Vec::new(),
+            effects: vec![
+                Effects::AutoSolve(Difficulty::Easy, Category::Web),
+                Effects::AutoSolve(Difficulty::Hard, Category::Misc),
+            ],
+            repeat: None,
+            reputation: None,
+        },
+    );
+    let preview = game.preview(id).unwrap();
+    assert_eq!(
+        preview.new_autosolve(),
+        vec![(Difficulty::Hard, Category::Misc)]
+    );
+}
+
+#[test]
+fn cash_per_min_improves() {
+    let base = Stats::default();
+    let rate = GameState::TYPICAL_CLICK_RATE;
+    let arrivals = GameState::ARRIVAL_RATE;
+    assert!(base.cash_per_min(rate, arrivals) > 0.0);
+    assert_eq!(base.cash_per_min(0.0, arrivals), 0.0);
+    let mut faster = base.clone();
+    faster.apply(&Effects::IncMultiplier(1.5));
+    assert!(faster.cash_per_min(rate, arrivals) > base.cash_per_min(rate, arrivals));
+    let mut richer = base.clone();
+    richer.apply(&Effects::IncCashMultiplier(2.0));
+    let doubled = base.cash_per_min(rate, arrivals) * 2.0;
+    assert!((richer.cash_per_min(rate, arrivals) - doubled).abs() < 1e-6);
+    let mut auto = base.clone();
+    auto.apply(&Effects::AutoSolve(Difficulty::Hard, Category::Misc));
+    assert!(auto.cash_per_min(0.0, arrivals) > 0.0);
+}
+
+#[test]
+fn cash_per_min_arrivals() {
+    let base = Stats::default();
+    // clicking fast enough that tickets run out, so more of them earns more
+    let rate = 100.0;
+    let arrivals = GameState::ARRIVAL_RATE;
+    assert!(base.cash_per_min(rate, arrivals * 2.0) > base.cash_per_min(rate, arrivals));
+    assert_eq!(base.cash_per_min(rate, 0.0), 0.0);
+}
+
+#[derive(Debug)]
 pub enum BuyError {
     Wallet(WalletError),
     UpgradeUnavailable,
@@ -275,49 +1207,245 @@ impl From<WalletError> for BuyError {
     }
 }
 
-/// Read upgrades.json and return the contents
-fn read_upgrades() -> Vec<Upgrade> {
-    let file = File::open("src/upgrades.json").expect("Could not open upgrades.json");
-    let mut buf_reader = BufReader::new(file);
-    let mut contents = String::new();
-    buf_reader
-        .read_to_string(&mut contents)
-        .expect("Could not read upgrades.json");
-    serde_json::from_str(&contents).unwrap()
-}
-
-/// Load upgrades from file and load into hashmap
+/// Map the upgrades in the catalog by ID
 fn load_upgrades() -> HashMap<String, Upgrade> {
-    let upgrades = read_upgrades();
     let mut hash = HashMap::new();
-    for upgrade in upgrades {
+    for upgrade in catalog::loaded().0 {
         hash.insert(upgrade.id.clone(), upgrade);
     }
     hash
 }
 
-/// Multiply the base by the multiplier and round up/down
-/// Should round up ((multiplier % 1) * 100)% of the time
-fn rand_round(base: u64, multiplier: f32) -> u64 {
-    let mut rng = rand::rng();
-    if rng.random::<f32>() < (multiplier % 1.0) {
-        (base as f32 * multiplier).ceil() as u64
+/// Multiply the base by the multiplier and round up/down at random
+/// Rounds up as often as the fractional part of the result, so on average it
+/// comes out to exactly `base * multiplier`
+fn rand_round(rng: &mut impl Rng, base: u64, multiplier: f32) -> u64 {
+    let value = base as f64 * multiplier as f64;
+    let whole = value.floor();
+    if rng.random::<f64>() < value - whole {
+        whole as u64 + 1
     } else {
-        (base as f32 * multiplier).floor() as u64
+        whole as u64
+    }
+}
+
+/// Multiply the base by the multiplier and round down, keeping the fraction
+/// in `carry` to be added to the next result
+/// No randomness, the total over many calls is always within 1 of exact
+fn carry_round(carry: &mut f64, base: u64, multiplier: f32) -> u64 {
+    let value = base as f64 * multiplier as f64 + *carry;
+    let whole = value.floor();
+    *carry = value - whole;
+    whole as u64
+}
+
+/// Pick one of the items at random, each as often as its weight says
+fn pick<T: Copy>(rng: &mut impl Rng, odds: &[(T, u32)]) -> T {
+    let total = odds.iter().map(|(_, weight)| weight).sum();
+    let mut roll = rng.random_range(0..total);
+    for (item, weight) in odds {
+        if roll < *weight {
+            return *item;
+        }
+        roll -= weight;
     }
+    panic!("Random number generated outside of range")
 }
 
-/// Struct for returning game stats affected by upgrades
+/// Random number of events in one unit of time, when on average there are
+/// `rate` of them (Poisson distribution)
+/// Uses Knuth's method, which is fine for the small rates used here
+fn poisson(rng: &mut impl Rng, rate: f64) -> u32 {
+    let limit = (-rate).exp();
+    let mut count = 0;
+    let mut product = rng.random::<f64>();
+    while product > limit {
+        count += 1;
+        product *= rng.random::<f64>();
+    }
+    count
+}
+
+/// Game stats affected by upgrades
+/// These aren't stored anywhere, they're always worked out from the effects of
+/// what has been bought, see `Stats::from_effects` and `GameState::stats`
 #[derive(Clone, PartialEq, Debug)]
 pub struct Stats {
+    /// How many "clicks" per user-click
     pub multiplier: f32,
+    /// How much cash per completed ticket multiplier
     pub cash_mult: f32,
+    /// How much XP per completed ticket multiplier
     pub xp_mult: f32,
+    /// What difficulty + category combos have autosolve enabled
     pub autosolve: HashSet<(Difficulty, Category)>,
+    /// Whether finished autosolve tickets close themselves
+    pub autoclose: bool,
+    /// Extra cash + XP multiplier for tickets of each priority
+    pub priority_mult: HashMap<Priority, f32>,
+}
+
+impl Default for Stats {
+    /// Stats with nothing bought
+    fn default() -> Self {
+        Self {
+            multiplier: 1.0,
+            cash_mult: 1.0,
+            xp_mult: 1.0,
+            autosolve: HashSet::new(),
+            autoclose: false,
+            priority_mult: HashMap::new(),
+        }
+    }
+}
+
+impl Stats {
+    /// The stats you get from all of these effects together
+    pub fn from_effects<'a>(effects: impl IntoIterator<Item = &'a Effects>) -> Self {
+        let mut stats = Self::default();
+        for effect in effects {
+            stats.apply(effect);
+        }
+        stats
+    }
+
+    /// Add one effect on top of these stats
+    pub fn apply(&mut self, effect: &Effects) {
+        match effect {
+            Effects::IncMultiplier(x) => self.multiplier *= x,
+            Effects::AutoSolve(diff, cat) => {
+                let _ = self.autosolve.insert((*diff, *cat));
+            }
+            Effects::IncCashMultiplier(x) => self.cash_mult *= x,
+            Effects::IncXPMultiplier(x) => self.xp_mult *= x,
+            Effects::AutoClose => self.autoclose = true,
+            Effects::IncPriorityMultiplier(priority, x) => {
+                *self.priority_mult.entry(*priority).or_insert(1.0) *= x
+            }
+        }
+    }
+
+    /// Rough guess at how much cash a minute these stats earn when the player
+    /// clicks `clicks_per_sec` times a second and `arrivals_per_sec` tickets
+    /// come in
+    /// Tickets that aren't autosolved get closed as fast as the clicks allow,
+    /// up to as fast as they arrive. Autosolved ones are assumed to always get
+    /// closed, and SLA breaches and the working set limit are ignored
+    pub fn cash_per_min(&self, clicks_per_sec: f64, arrivals_per_sec: f64) -> f64 {
+        let odds = |total: u32, weight: u32| weight as f64 / total as f64;
+        let difficulties: u32 = GameState::DIFFICULTY_ODDS.iter().map(|(_, x)| x).sum();
+        let categories: u32 = GameState::CATEGORY_ODDS.iter().map(|(_, x)| x).sum();
+        let priorities: u32 = GameState::PRIORITY_ODDS.iter().map(|(_, x)| x).sum();
+
+        // chance of a manual ticket, and clicks + cash from them on average
+        let (mut manual, mut manual_clicks, mut manual_cash) = (0.0, 0.0, 0.0);
+        let mut auto_cash = 0.0;
+        for (diff, diff_weight) in GameState::DIFFICULTY_ODDS {
+            let (clicks, cash) = match diff {
+                Difficulty::Easy => (Ticket::EASY_GOAL, GameState::BASE_EASY_CASH),
+                Difficulty::Med => (Ticket::MED_GOAL, GameState::BASE_MED_CASH),
+                Difficulty::Hard => (Ticket::HARD_GOAL, GameState::BASE_HARD_CASH),
+            };
+            // a ticket is finished one click after reaching its goal
+            let (clicks, cash) = (clicks as f64 + 1.0, cash as f64);
+            for (cat, cat_weight) in GameState::CATEGORY_ODDS {
+                let chance = odds(difficulties, diff_weight) * odds(categories, cat_weight);
+                if self.autosolve.contains(&(diff, cat)) {
+                    auto_cash += chance * cash;
+                } else {
+                    manual += chance;
+                    manual_clicks += chance * clicks;
+                    manual_cash += chance * cash;
+                }
+            }
+        }
+
+        let arrivals = arrivals_per_sec;
+        let manual_tickets = if manual_clicks > 0.0 {
+            let clicked = clicks_per_sec * self.multiplier as f64 / (manual_clicks / manual);
+            clicked.min(arrivals * manual)
+        } else {
+            0.0
+        };
+        let cash_per_sec = if manual > 0.0 {
+            manual_tickets * manual_cash / manual + arrivals * auto_cash
+        } else {
+            arrivals * auto_cash
+        };
+
+        let priority: f64 = GameState::PRIORITY_ODDS
+            .iter()
+            .map(|(p, weight)| {
+                let base = match p {
+                    Priority::P1 => GameState::P1_PAYOUT,
+                    Priority::P2 => GameState::P2_PAYOUT,
+                    Priority::P3 => GameState::P3_PAYOUT,
+                    Priority::P4 => GameState::P4_PAYOUT,
+                };
+                let mult = base * self.priority_mult.get(p).unwrap_or(&1.0);
+                odds(priorities, *weight) * mult as f64
+            })
+            .sum();
+        cash_per_sec * 60.0 * self.cash_mult as f64 * priority
+    }
+}
+
+/// What buying an upgrade would do to the stats
+#[derive(Clone, PartialEq, Debug)]
+pub struct Preview {
+    pub before: Stats,
+    pub after: Stats,
+    /// How many tickets a second the game gets, for `Stats::cash_per_min`
+    pub arrival_rate: f64,
+}
+
+impl Preview {
+    /// Autosolve combos the upgrade would turn on
+    pub fn new_autosolve(&self) -> Vec<(Difficulty, Category)> {
+        let mut new: Vec<_> = self
+            .after
+            .autosolve
+            .difference(&self.before.autosolve)
+            .copied()
+            .collect();
+        new.sort_by_key(|(diff, cat)| (*diff as u8, *cat as u8));
+        new
+    }
+}
+
+/// How fractional clicks and payouts get turned into whole numbers
+#[derive(Clone, Copy, PartialEq, Debug, Default, Deserialize, Serialize)]
+pub enum Rounding {
+    /// Round up or down at random, see `rand_round`
+    #[default]
+    Random,
+    /// Round down and carry the fraction over, see `carry_round`
+    Carry,
+}
+
+/// Fractions left over by `Rounding::Carry`
+#[derive(Clone, Copy, PartialEq, Debug, Default, Deserialize, Serialize)]
+pub struct Remainders {
+    clicks: f64,
+    cash: f64,
+    xp: f64,
+}
+
+/// What happened while the game was closed
+#[derive(Clone, PartialEq, Debug)]
+pub struct Offline {
+    /// How many seconds of progress were simulated
+    pub secs: i64,
+    /// How many tickets were closed
+    pub tickets: u64,
+    pub cash: Amount,
+    pub xp: Amount,
 }
 
 /// Data needed for the main game loop
-#[derive(Clone, Debug)]
+/// The upgrade catalog isn't saved, it's reloaded from upgrades.json so that
+/// saves only refer to upgrades by ID
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct GameState {
     /// Queue of unfinished tickets
     queue: Vec<Ticket>,
@@ -325,18 +1453,53 @@ pub struct GameState {
     wallet: Currency,
     /// Tickets that are currently being worked on
     working: Vec<Ticket>,
-    /// How many "clicks" per user-click
-    multiplier: f32,
-    /// How much cash per completed ticket multiplier
-    cash_mult: f32,
-    /// How much XP per completed ticket multiplier
-    xp_mult: f32,
-    /// What difficulty + category combos have autosolve enabled
-    autosolve: HashSet<(Difficulty, Category)>,
     /// All possible upgrades mapped by ID
+    #[serde(skip, default = "load_upgrades")]
     upgrades: HashMap<String, Upgrade>,
     /// ID of any purchased upgrades
     purchased: HashSet<String>,
+    /// How many times each repeatable upgrade has been bought
+    levels: HashMap<String, u32>,
+    /// Effects that apply on top of the purchased upgrades, e.g. buffs
+    modifiers: Vec<Effects>,
+    /// Seniority, perks and lifetime earnings, which survive promotions
+    career: Career,
+    /// How happy customers are, up to `MAX_REPUTATION`
+    /// Kept out of the wallet since it's never spent, and it survives
+    /// promotions
+    reputation: Amount,
+    /// How many tickets have been closed in this run
+    tickets_closed: u64,
+    /// How many of the closed tickets missed their SLA
+    tickets_breached: u64,
+    /// Unix timestamp of the last tick, used to catch up on offline progress
+    last_tick: Option<i64>,
+    /// How fractional clicks and payouts are rounded
+    rounding: Rounding,
+    /// Average number of new tickets per second
+    arrival_rate: f64,
+    /// Fractions waiting to be paid out when rounding by carrying
+    remainders: Remainders,
+    /// Source of every random thing in the game, saved so a loaded game
+    /// carries on with the same numbers
+    rng: GameRng,
+    /// Something happened that should be saved soon
+    #[serde(skip)]
+    save_requested: bool,
+    /// Game time that languishing tickets have been charged for up to
+    /// Not saved, a loaded game just starts counting again
+    #[serde(skip)]
+    languished: Option<i64>,
+    /// Offline progress is being caught up on, so nothing costs reputation
+    #[serde(skip)]
+    offline: bool,
+}
+
+impl Default for GameState {
+    /// A new game with a random seed, same as `GameState::new`
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl GameState {
@@ -346,18 +1509,91 @@ impl GameState {
     const BASE_MED_XP: u64 = 10;
     const BASE_HARD_CASH: u64 = 60;
     const BASE_HARD_XP: u64 = 20;
+    /// Most offline progress that will be caught up on, 8 hours
+    pub const MAX_OFFLINE_SECS: i64 = 8 * 60 * 60;
+    /// Average number of new tickets per second, unless it's changed with
+    /// `set_arrival_rate`
+    pub const ARRIVAL_RATE: f64 = 0.2;
+    /// Most tickets that can be worked on at once
+    pub const WORKING_CAPACITY: usize = 4;
+    /// Most tickets that can be waiting in the queue
+    pub const MAX_QUEUE: usize = 30;
+    /// How much of the usual reward a ticket pays after missing its SLA
+    pub const BREACH_PAYOUT: f32 = 0.5;
+    /// Reward multiplier for each priority
+    pub const P1_PAYOUT: f32 = 2.0;
+    pub const P2_PAYOUT: f32 = 1.5;
+    pub const P3_PAYOUT: f32 = 1.0;
+    pub const P4_PAYOUT: f32 = 0.75;
+    /// Most reputation the player can have
+    pub const MAX_REPUTATION: u64 = 100;
+    /// Reputation for closing a ticket within the first half of its SLA
+    pub const FAST_REPUTATION: u64 = 2;
+    /// Reputation for closing a ticket later on, but still within its SLA
+    pub const CLOSE_REPUTATION: u64 = 1;
+    /// Reputation lost for closing a ticket after it missed its SLA
+    pub const BREACH_REPUTATION: u64 = 5;
+    /// Reputation lost every minute for each ticket in working that's past its
+    /// SLA
+    pub const LANGUISH_REPUTATION: u64 = 1;
+    /// Reputation needed for each step of the difficulty mix towards hard
+    /// tickets, see `difficulty_odds`
+    pub const REPUTATION_PER_STEP: u64 = 25;
+    /// How often new tickets are each difficulty, out of 10, with no
+    /// reputation
+    const DIFFICULTY_ODDS: [(Difficulty, u32); 3] = [
+        (Difficulty::Easy, 4),
+        (Difficulty::Med, 3),
+        (Difficulty::Hard, 3),
+    ];
+    /// How often new tickets are each category, out of 20
+    const CATEGORY_ODDS: [(Category, u32); 5] = [
+        (Category::Misc, 7),
+        (Category::Windows, 4),
+        (Category::Linux, 3),
+        (Category::Network, 3),
+        (Category::Web, 3),
+    ];
+    /// How often new tickets are each priority, out of 10
+    const PRIORITY_ODDS: [(Priority, u32); 4] = [
+        (Priority::P1, 1),
+        (Priority::P2, 2),
+        (Priority::P3, 4),
+        (Priority::P4, 3),
+    ];
+    /// A few clicks a second, about what a person keeps up while playing
+    pub const TYPICAL_CLICK_RATE: f64 = 3.0;
 
     pub fn new() -> Self {
+        Self::with_rng(GameRng::from_entropy())
+    }
+
+    /// A new game where everything random is decided by `seed`
+    pub fn with_seed(seed: u64) -> Self {
+        Self::with_rng(GameRng::new(seed))
+    }
+
+    fn with_rng(rng: GameRng) -> Self {
         Self {
             queue: Vec::new(),
             wallet: Currency::new(),
             working: Vec::new(),
-            multiplier: 1.0,
-            cash_mult: 1.0,
-            xp_mult: 1.0,
-            autosolve: HashSet::new(),
             upgrades: load_upgrades(),
             purchased: HashSet::new(),
+            levels: HashMap::new(),
+            modifiers: Vec::new(),
+            career: Career::default(),
+            reputation: Amount::ZERO,
+            tickets_closed: 0,
+            tickets_breached: 0,
+            last_tick: None,
+            rounding: Rounding::Random,
+            arrival_rate: Self::ARRIVAL_RATE,
+            remainders: Remainders::default(),
+            rng,
+            save_requested: false,
+            languished: None,
+            offline: false,
         }
     }
 
@@ -369,13 +1605,118 @@ impl GameState {
         &self.working
     }
 
+    /// IDs of every upgrade that has been bought
+    pub fn purchased(&self) -> &HashSet<String> {
+        &self.purchased
+    }
+
+    /// Every upgrade in the catalog, mapped by ID
+    pub fn upgrades(&self) -> &HashMap<String, Upgrade> {
+        &self.upgrades
+    }
+
+    /// How many tickets have been closed in this run
+    pub fn tickets_closed(&self) -> u64 {
+        self.tickets_closed
+    }
+
+    /// How many closed tickets missed their SLA
+    pub fn tickets_breached(&self) -> u64 {
+        self.tickets_breached
+    }
+
+    /// Unix timestamp of the last tick, which is "now" as far as the game knows
+    pub fn last_tick(&self) -> Option<i64> {
+        self.last_tick
+    }
+
+    /// Seed the game's random numbers started from
+    pub fn seed(&self) -> u64 {
+        self.rng.seed()
+    }
+
+    pub fn rounding(&self) -> Rounding {
+        self.rounding
+    }
+
+    /// Switch how fractional clicks and payouts are rounded
+    pub fn set_rounding(&mut self, rounding: Rounding) {
+        self.rounding = rounding;
+    }
+
+    pub fn arrival_rate(&self) -> f64 {
+        self.arrival_rate
+    }
+
+    /// Change how many new tickets come in each second on average
+    /// Negative rates (and NaN) mean no new tickets at all
+    pub fn set_arrival_rate(&mut self, rate: f64) {
+        self.arrival_rate = if rate > 0.0 { rate } else { 0.0 };
+    }
+
+    /// Turn `base * multiplier` into a whole number the way `rounding` says
+    /// `carry` is where the leftover fraction is kept when carrying
+    fn round(&mut self, carry: fn(&mut Remainders) -> &mut f64, base: u64, multiplier: f32) -> u64 {
+        match self.rounding {
+            Rounding::Random => rand_round(&mut self.rng, base, multiplier),
+            Rounding::Carry => carry_round(carry(&mut self.remainders), base, multiplier),
+        }
+    }
+
+    /// Returns whether anything important has happened since the last call
+    pub fn take_save_request(&mut self) -> bool {
+        std::mem::take(&mut self.save_requested)
+    }
+
+    /// Stats from the purchased upgrades and any modifiers
+    /// Upgrades that aren't in the catalog any more don't count
     pub fn stats(&self) -> Stats {
-        Stats {
-            multiplier: self.multiplier,
-            cash_mult: self.cash_mult,
-            xp_mult: self.xp_mult,
-            autosolve: self.autosolve.clone(),
+        // sorted so multipliers are always multiplied in the same order
+        let mut purchased: Vec<&String> = self.purchased.iter().collect();
+        purchased.sort();
+        let effects = purchased
+            .into_iter()
+            .filter_map(|id| self.upgrades.get(id))
+            .flat_map(|x| iter::repeat_n(&x.effects, self.level(&x.id) as usize).flatten())
+            .chain(self.career.effects())
+            .chain(&self.modifiers);
+        Stats::from_effects(effects)
+    }
+
+    /// The stats now, and what they would be after buying the upgrade
+    /// Returns None if there's no upgrade with that ID
+    pub fn preview(&self, id: &str) -> Option<Preview> {
+        let upgrade = self.upgrades.get(id)?;
+        let before = self.stats();
+        let mut after = before.clone();
+        for effect in &upgrade.effects {
+            after.apply(effect);
         }
+        Some(Preview {
+            before,
+            after,
+            arrival_rate: self.arrival_rate,
+        })
+    }
+
+    /// Effects on top of the purchased upgrades
+    pub fn modifiers(&self) -> &Vec<Effects> {
+        &self.modifiers
+    }
+
+    /// Add an effect on top of the purchased upgrades
+    pub fn add_modifier(&mut self, effect: Effects) {
+        self.modifiers.push(effect);
+    }
+
+    /// Remove every modifier, leaving only the purchased upgrades
+    pub fn clear_modifiers(&mut self) {
+        self.modifiers.clear();
+    }
+
+    /// How many tickets are waiting to be worked on
+    pub fn backlog(&self) -> usize {
+        self.queue.len()
     }
 
     pub fn init_queue(&mut self) {
@@ -385,95 +1726,309 @@ impl GameState {
         }
     }
 
+    /// How often new tickets are each difficulty, out of 10
+    /// Every `REPUTATION_PER_STEP` reputation moves one easy ticket in 10 over
+    /// to hard, as customers trust the helpdesk with more (and better paid)
+    /// problems
+    fn difficulty_odds(reputation: Amount) -> [(Difficulty, u32); 3] {
+        let [(easy, easy_odds), med, (hard, hard_odds)] = Self::DIFFICULTY_ODDS;
+        let steps = (reputation.as_f64() / Self::REPUTATION_PER_STEP as f64) as u32;
+        let steps = steps.min(easy_odds - 1);
+        [(easy, easy_odds - steps), med, (hard, hard_odds + steps)]
+    }
+
     /// Add a random new ticket to the queue
     pub fn spawn_ticket(&mut self) {
-        let mut rng = rand::rng();
-        let difficulty = match rng.random_range(0..10) {
-            0..=3 => Difficulty::Easy,
-            4..=6 => Difficulty::Med,
-            7..=10 => Difficulty::Hard,
-            _ => panic!("Random number generated outside of range"),
-        };
-        let category = match rng.random_range(0..20) {
-            0..=6 => Category::Misc,
-            7..=10 => Category::Windows,
-            11..=13 => Category::Linux,
-            14..=16 => Category::Network,
-            17..=20 => Category::Web,
-            _ => panic!("Random number generated outside of range"),
-        };
+        let odds = Self::difficulty_odds(self.reputation());
+        let rng = &mut self.rng;
+        let difficulty = pick(rng, &odds);
+        let category = pick(rng, &Self::CATEGORY_ODDS);
+        let priority = pick(rng, &Self::PRIORITY_ODDS);
         let name = format!("{:?} issue #{:04}", category, rng.random_range(1000..9999));
-        self.queue.push(Ticket::new(difficulty, category, &name));
+        let mut ticket = Ticket::new(difficulty, category, &name);
+        ticket.set_priority(priority);
+        if let Some(now) = self.last_tick {
+            ticket.open(now);
+        }
+        self.queue.push(ticket);
     }
 
-    /// Move one from queue to working
+    /// Move the most urgent ticket from queue to working
+    /// Tickets with the same priority are taken oldest first
     pub fn assign_next_ticket(&mut self) {
-        if let Some(ticket) = self.queue.pop() {
+        let next = self
+            .queue
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, ticket)| *ticket.priority())
+            .map(|(i, _)| i);
+        if let Some(i) = next {
+            let ticket = self.queue.remove(i);
             self.working.push(ticket);
         }
     }
 
+    /// Pull tickets from the queue until the working set is full
+    fn fill_working(&mut self) {
+        while self.working.len() < Self::WORKING_CAPACITY && !self.queue.is_empty() {
+            self.assign_next_ticket();
+        }
+    }
+
+    /// New tickets come in at random, about `arrival_rate` per second
+    /// The queue stops growing once it hits `MAX_QUEUE`
+    fn ticket_arrivals(&mut self) {
+        let arrivals = poisson(&mut self.rng, self.arrival_rate);
+        for _ in 0..arrivals {
+            if self.queue.len() >= Self::MAX_QUEUE {
+                break;
+            }
+            self.spawn_ticket();
+        }
+    }
+
     /// Process a click on a ticket
     pub fn click_ticket(&mut self, index: usize) {
-        if let Some(ticket) = self.working.get_mut(index) {
-            let clicks = rand_round(1, self.multiplier) as u16;
+        if index < self.working.len() {
+            let clicks = self.round(|r| &mut r.clicks, 1, self.stats().multiplier) as u16;
+            let ticket = &mut self.working[index];
             ticket.click(clicks);
             if ticket.is_complete() {
-                let (cash, xp) = match ticket.difficulty() {
-                    Difficulty::Easy => (
-                        rand_round(Self::BASE_EASY_CASH, self.cash_mult),
-                        rand_round(Self::BASE_EASY_XP, self.xp_mult),
-                    ),
-                    Difficulty::Med => (
-                        rand_round(Self::BASE_MED_CASH, self.cash_mult),
-                        rand_round(Self::BASE_MED_XP, self.xp_mult),
-                    ),
-                    Difficulty::Hard => (
-                        rand_round(Self::BASE_HARD_CASH, self.cash_mult),
-                        rand_round(Self::BASE_HARD_XP, self.xp_mult),
-                    ),
-                };
-                self.wallet.add_cash(cash);
-                self.wallet.add_xp(xp);
+                self.close_ticket(index);
+            }
+        }
+    }
+
+    /// How much more than usual a ticket of this priority pays out, including
+    /// any upgrades for that priority
+    fn priority_payout(&self, stats: &Stats, priority: &Priority) -> f32 {
+        let base = match priority {
+            Priority::P1 => Self::P1_PAYOUT,
+            Priority::P2 => Self::P2_PAYOUT,
+            Priority::P3 => Self::P3_PAYOUT,
+            Priority::P4 => Self::P4_PAYOUT,
+        };
+        base * stats.priority_mult.get(priority).unwrap_or(&1.0)
+    }
+
+    /// Returns the player's reputation
+    pub fn reputation(&self) -> Amount {
+        self.reputation
+    }
+
+    /// Gain reputation, up to `MAX_REPUTATION`
+    fn gain_reputation(&mut self, n: u64) {
+        self.reputation = (self.reputation + n.into()).min(Self::MAX_REPUTATION.into());
+    }
+
+    /// Lose reputation, stopping at 0
+    /// Nothing is lost while catching up, the player can't do anything about
+    /// tickets going late while the game is closed
+    fn lose_reputation(&mut self, n: u64) {
+        if self.offline {
+            return;
+        }
+        self.reputation = self.reputation - n.into();
+    }
+
+    /// Reputation earned for closing `ticket` at `now`, or None if it missed
+    /// its SLA. Tickets closed without a clock still count as on time
+    fn close_reputation(ticket: &Ticket, now: Option<i64>) -> Option<u64> {
+        let (Some(now), Some(opened), Some(deadline)) = (now, ticket.opened(), ticket.deadline())
+        else {
+            return Some(Self::CLOSE_REPUTATION);
+        };
+        if now > deadline {
+            None
+        } else if now - opened <= (deadline - opened) / 2 {
+            Some(Self::FAST_REPUTATION)
+        } else {
+            Some(Self::CLOSE_REPUTATION)
+        }
+    }
+
+    /// Pay out for a finished ticket and remove it from the working set
+    /// Higher priority tickets pay out more
+    /// Tickets that missed their SLA only pay out `BREACH_PAYOUT` as much
+    /// and cost reputation, ones closed quickly earn extra
+    fn close_ticket(&mut self, index: usize) {
+        let ticket = self.working.remove(index);
+        let breached = self.last_tick.is_some_and(|now| ticket.breached(now));
+        match Self::close_reputation(&ticket, self.last_tick) {
+            Some(n) => self.gain_reputation(n),
+            None => self.lose_reputation(Self::BREACH_REPUTATION),
+        }
+        let penalty = if breached { Self::BREACH_PAYOUT } else { 1.0 };
+        let stats = self.stats();
+        let priority = self.priority_payout(&stats, ticket.priority()) * penalty;
+        let (cash_mult, xp_mult) = (stats.cash_mult * priority, stats.xp_mult * priority);
+        let (cash, xp) = match ticket.difficulty() {
+            Difficulty::Easy => (Self::BASE_EASY_CASH, Self::BASE_EASY_XP),
+            Difficulty::Med => (Self::BASE_MED_CASH, Self::BASE_MED_XP),
+            Difficulty::Hard => (Self::BASE_HARD_CASH, Self::BASE_HARD_XP),
+        };
+        let reward = Currency::new()
+            .with(Resource::Cash, self.round(|r| &mut r.cash, cash, cash_mult))
+            .with(Resource::Xp, self.round(|r| &mut r.xp, xp, xp_mult));
+        self.wallet.add_all(&reward);
+        self.career.earn(reward.cash());
+        self.tickets_closed += 1;
+        if breached {
+            self.tickets_breached += 1;
+        }
+        self.save_requested = true;
+        self.fill_working();
+    }
 
-                // Remove finished tickets
-                let _ = self.working.remove(index);
+    /// One step of the idle game, which happens once per second
+    pub fn tick(&mut self, clock: &impl Clock) {
+        self.last_tick = Some(clock.now());
+        self.second();
+    }
+
+    /// Run `dt` seconds of game time without looking at any clock
+    /// Game time carries on from the last tick, or from 0 if there hasn't
+    /// been one, so the game can be driven by tests, bots and simulations
+    pub fn step(&mut self, dt: i64) {
+        let start = self.last_tick.unwrap_or(0);
+        for i in 1..=dt {
+            self.last_tick = Some(start + i);
+            self.second();
+        }
+    }
+
+    /// Everything that happens in one second of game time
+    fn second(&mut self) {
+        self.ticket_arrivals();
+        self.start_slas();
+        self.languish();
+        self.autosolve();
+        self.fill_working();
+    }
+
+    /// Start the SLA timer on any ticket that doesn't have one yet, e.g. ones
+    /// from before SLAs existed or made before the first tick
+    fn start_slas(&mut self) {
+        if let Some(now) = self.last_tick {
+            for ticket in self.queue.iter_mut().chain(self.working.iter_mut()) {
+                ticket.open(now);
             }
         }
     }
 
+    /// Lose reputation for every ticket still in working after missing its
+    /// SLA, once for each whole minute since the last charge
+    /// Ticks don't always land every second, so any minutes that passed
+    /// between two of them are all charged at once
+    fn languish(&mut self) {
+        let Some(now) = self.last_tick else {
+            return;
+        };
+        let since = *self.languished.get_or_insert(now);
+        let minutes = (now - since) / 60;
+        if minutes <= 0 {
+            return;
+        }
+        let late: usize = (1..=minutes)
+            .map(|i| since + i * 60)
+            .map(|at| self.working.iter().filter(|x| x.breached(at)).count())
+            .sum();
+        self.languished = Some(since + minutes * 60);
+        self.lose_reputation(late as u64 * Self::LANGUISH_REPUTATION);
+    }
+
+    /// Run the ticks that would have happened between the last tick and now
+    /// while the game was closed, up to `max_secs` worth of them
+    /// Late tickets don't cost any reputation while catching up
+    /// Returns None if there's nothing to catch up on
+    pub fn catch_up(&mut self, clock: &impl Clock, max_secs: i64) -> Option<Offline> {
+        let now = clock.now();
+        let last = self.last_tick?;
+        let secs = (now - last).clamp(0, max_secs);
+        if secs == 0 {
+            self.last_tick = Some(now);
+            return None;
+        }
+
+        let wallet = self.wallet.clone();
+        let closed = self.tickets_closed;
+        // the simulated seconds are the ones right before now
+        self.last_tick = Some(now - secs);
+        self.offline = true;
+        self.step(secs);
+        self.offline = false;
+        Some(Offline {
+            secs,
+            tickets: self.tickets_closed - closed,
+            cash: self.wallet.cash() - wallet.cash(),
+            xp: self.wallet.xp() - wallet.xp(),
+        })
+    }
+
     /// Click once on any ticket that is available and matches the currently
     /// bought autosolve upgrades
+    /// If auto-close has been bought, finished autosolve tickets are closed too
     pub fn autosolve(&mut self) {
-        for (diff, cat) in &self.autosolve {
+        let stats = self.stats();
+        for (diff, cat) in &stats.autosolve {
             for ticket in &mut self.working {
                 if ticket.difficulty() == diff && ticket.category() == cat {
                     ticket.click(1);
                 }
             }
         }
+
+        if stats.autoclose {
+            // backwards so removing a ticket doesn't shift the ones still to check
+            for i in (0..self.working.len()).rev() {
+                let ticket = &self.working[i];
+                let solved = stats
+                    .autosolve
+                    .contains(&(*ticket.difficulty(), *ticket.category()));
+                if solved && ticket.is_complete() {
+                    self.close_ticket(i);
+                }
+            }
+        }
+    }
+
+    /// How many times an upgrade has been bought
+    /// Always 0 or 1 for upgrades that aren't repeatable
+    pub fn level(&self, id: &str) -> u32 {
+        match self.levels.get(id) {
+            Some(level) => *level,
+            None => self.purchased.contains(id) as u32,
+        }
+    }
+
+    /// What buying the next level of an upgrade costs right now
+    pub fn price(&self, upgrade: &Upgrade) -> Currency {
+        upgrade.cost_at(self.level(&upgrade.id))
     }
 
     /// Check if an upgrade is available to buy
     /// It's available to buy if:
     /// - It exists in the upgrade hashmap
-    /// - It has not already been purchased
+    /// - It has not already been purchased, or is repeatable and hasn't been
+    ///   bought as many times as it can be
     /// - All of its prerequisite purchases have been made
     ///
     /// Note that it does **not** check if the upgrade can be afforded
     fn upgrade_available(&self, id: &String) -> bool {
-        // already bought it, can't buy it again
-        if self.purchased.contains(id) {
+        // not there, can't buy it
+        let Some(upgrade) = self.upgrades.get(id) else {
             return false;
         };
-        // not there, can't buy it
-        if !self.upgrades.contains_key(id) {
+        // already bought it as many times as it allows, can't buy it again
+        if upgrade.max_level().is_some_and(|max| self.level(id) >= max) {
             return false;
         };
+        // customers don't trust the helpdesk enough yet
+        if upgrade.reputation.is_some_and(|n| self.reputation() < n) {
+            return false;
+        }
 
-        self.upgrades
-            .get(id)
-            .unwrap()
+        upgrade
             .requires
             .iter()
             .all(|req| self.purchased.contains(req))
@@ -491,7 +2046,8 @@ impl GameState {
         avail
     }
 
-    /// Buy an upgrade and apply its effects
+    /// Buy an upgrade (or the next level of a repeatable one) and apply its
+    /// effects
     /// Will return an error if:
     /// - Upgrade isn't available
     /// - Upgrade is too expensive
@@ -501,29 +2057,59 @@ impl GameState {
             return Err(BuyError::UpgradeUnavailable);
         }
 
-        let effects = {
-            let upgrade = self.upgrades.get(id).unwrap();
-            self.wallet.spend(upgrade.cost.cash(), upgrade.cost.xp())?;
-            self.purchased.insert(upgrade.id.clone());
-            upgrade.effects.clone()
-        };
-        self.apply_upgrade(&effects);
+        let upgrade = self.upgrades.get(id).unwrap();
+        let price = self.price(upgrade);
+        self.wallet.spend(&price)?;
+        if upgrade.repeat.is_some() {
+            *self.levels.entry(upgrade.id.clone()).or_insert(0) += 1;
+        }
+        self.purchased.insert(upgrade.id.clone());
+        self.save_requested = true;
 
         Ok(())
     }
 
-    /// Update the GameStruct with the provided effects
-    /// Should only be called from buy_upgrade()
-    fn apply_upgrade(&mut self, effects: &Vec<Effects>) {
-        for up in effects {
-            match up {
-                Effects::IncMultiplier(x) => self.multiplier *= x,
-                Effects::AutoSolve(diff, cat) => {
-                    let _ = self.autosolve.insert((*diff, *cat));
-                }
-                Effects::IncCashMultiplier(x) => self.cash_mult *= x,
-                Effects::IncXPMultiplier(x) => self.xp_mult *= x,
-            }
+    /// Swap in a different upgrade catalog, e.g. after upgrades.json was edited
+    /// Stats come from the catalog, so the run keeps its purchases but they
+    /// use the new numbers
+    /// Purchased upgrades that aren't in the new catalog do nothing, but stay
+    /// purchased in case they come back
+    pub fn reload_upgrades(&mut self, upgrades: Vec<Upgrade>) {
+        self.upgrades = upgrades.into_iter().map(|x| (x.id.clone(), x)).collect();
+    }
+
+    pub fn career(&self) -> &Career {
+        &self.career
+    }
+
+    /// Trade in this run for seniority and start a new one from scratch
+    /// The wallet, purchased upgrades, tickets and modifiers are all reset.
+    /// Only the career, the rounding setting, the clock and the random number
+    /// generator carry over
+    /// Returns how much seniority the promotion gave
+    pub fn promote(&mut self) -> Result<u64, PromotionError> {
+        if self.career.pending() == 0 {
+            return Err(PromotionError::NothingEarned);
         }
+        let points = self.career.promote();
+        let rng = self.rng.clone();
+        let old = std::mem::replace(self, Self::with_rng(rng));
+        self.upgrades = old.upgrades;
+        self.career = old.career;
+        self.reputation = old.reputation;
+        self.rounding = old.rounding;
+        self.arrival_rate = old.arrival_rate;
+        self.last_tick = old.last_tick;
+        self.init_queue();
+        self.save_requested = true;
+        Ok(points)
+    }
+
+    /// Spend seniority on a perk, which applies straight away and keeps
+    /// applying in every later run
+    pub fn buy_perk(&mut self, id: &str) -> Result<(), PromotionError> {
+        self.career.buy_perk(id)?;
+        self.save_requested = true;
+        Ok(())
     }
 }
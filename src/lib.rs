@@ -0,0 +1,15 @@
+//! The game engine, with no UI attached
+//! The desktop app in main.rs is one way of driving it, but anything that can
+//! send `Command`s and call `GameState::step` can play the game
+
+pub mod catalog;
+pub mod clock;
+pub mod command;
+pub mod currency;
+pub mod game;
+pub mod promotion;
+pub mod rng;
+pub mod save;
+pub mod strategy;
+pub mod ticket;
+pub mod upgrade;
@@ -1,9 +1,13 @@
 mod app;
-mod currency;
-mod game;
-mod ticket;
-mod upgrade;
+
+use std::env;
+use std::path::PathBuf;
+use ticket_tycoon::catalog;
 
 fn main() {
+    // `--upgrades <file>` plays with a different upgrade catalog
+    if let Some(path) = env::args().skip_while(|x| x != "--upgrades").nth(1) {
+        catalog::set_override(PathBuf::from(path));
+    }
     dioxus::launch(app::app);
 }
@@ -0,0 +1,215 @@
+use crate::catalog;
+use crate::currency::*;
+use crate::upgrade::*;
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::OnceLock;
+
+#[test]
+fn perks_valid() {
+    let ids: HashSet<&str> = perks().iter().map(|x| x.id.as_str()).collect();
+    assert_eq!(ids.len(), perks().len());
+    for perk in perks() {
+        assert!(perk.cost > 0, "{} is free", perk.id);
+        for req in &perk.requires {
+            assert!(ids.contains(req.as_str()), "{} requires {}", perk.id, req);
+        }
+    }
+}
+
+#[test]
+fn points_grow_slowly() {
+    assert_eq!(Career::points(0), 0);
+    assert_eq!(Career::points(Career::PROMOTION_CASH - 1), 0);
+    assert_eq!(Career::points(Career::PROMOTION_CASH), 1);
+    assert_eq!(Career::points(Career::PROMOTION_CASH * 4), 2);
+    assert_eq!(Career::points(Career::PROMOTION_CASH * 100), 10);
+}
+
+#[test]
+fn pending_counts_lifetime() {
+    let mut career = Career::default();
+    career.earn(Career::PROMOTION_CASH);
+    assert_eq!(career.pending(), 1);
+    assert_eq!(career.promote(), 1);
+    assert_eq!(career.pending(), 0);
+    // the next point needs 4 times as much in total, not just as much again
+    career.earn(Career::PROMOTION_CASH);
+    assert_eq!(career.pending(), 0);
+    career.earn(Career::PROMOTION_CASH * 2);
+    assert_eq!(career.pending(), 1);
+    assert_eq!(career.seniority(), 1);
+    assert_eq!(career.promotions(), 1);
+}
+
+#[test]
+fn buy_perks() {
+    let mut career = Career {
+        seniority: 2,
+        ..Career::default()
+    };
+    assert!(matches!(
+        career.buy_perk("window_desk"),
+        Err(PromotionError::PerkUnavailable)
+    ));
+    assert!(career.buy_perk("mentor").is_ok());
+    assert!(matches!(
+        career.buy_perk("mentor"),
+        Err(PromotionError::PerkUnavailable)
+    ));
+    assert!(matches!(
+        career.buy_perk("window_desk"),
+        Err(PromotionError::NotEnoughSeniority)
+    ));
+    assert_eq!(career.seniority(), 1);
+    assert!(career.perks().contains("mentor"));
+    assert_eq!(career.effects(), vec![&Effects::IncMultiplier(1.25)]);
+    assert!(career.avail_perks().iter().any(|x| x.id == "window_desk"));
+    assert!(!career.avail_perks().iter().any(|x| x.id == "mentor"));
+}
+
+/// The built in permanent upgrades, bought with seniority
+const PERKS: &str = include_str!("perks.json");
+
+/// A permanent upgrade that carries over between promotions
+/// Like `Upgrade` this is just data from a file
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct Perk {
+    /// Unique ID for the perk (internal)
+    pub id: String,
+    /// Name of the perk (for display)
+    pub name: String,
+    /// Perk description
+    pub desc: String,
+    /// Cost in seniority
+    pub cost: u64,
+    /// ID of perks that are needed before this becomes available
+    pub requires: Vec<String>,
+    /// What the perk does, on top of the upgrades bought in each run
+    pub effects: Vec<Effects>,
+}
+
+/// Every perk there is
+pub fn perks() -> &'static [Perk] {
+    static PARSED: OnceLock<Vec<Perk>> = OnceLock::new();
+    PARSED.get_or_init(|| {
+        catalog::parse_perks(PERKS)
+            .expect("Built in perks.json should be valid, the tests check it")
+    })
+}
+
+#[derive(Debug)]
+pub enum PromotionError {
+    /// Not enough has been earned since the last promotion for any seniority
+    NothingEarned,
+    /// The perk doesn't exist, is already owned or needs other perks first
+    PerkUnavailable,
+    NotEnoughSeniority,
+}
+
+/// Everything that carries over when the player gets promoted
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct Career {
+    /// Cash earned across every run
+    lifetime_cash: Amount,
+    /// Seniority that can be spent on perks
+    seniority: u64,
+    /// Seniority handed out so far, spent or not
+    earned: u64,
+    /// How many times the player has been promoted
+    promotions: u32,
+    /// IDs of the perks that have been bought
+    perks: HashSet<String>,
+}
+
+impl Career {
+    /// Lifetime cash needed for the first point of seniority
+    pub const PROMOTION_CASH: u64 = 20_000;
+
+    /// Total seniority that `lifetime_cash` is worth
+    /// It goes up with the square root, so each point needs more cash than
+    /// the one before
+    pub fn points(lifetime_cash: impl Into<Amount>) -> u64 {
+        (lifetime_cash.into().as_f64() / Self::PROMOTION_CASH as f64).sqrt() as u64
+    }
+
+    pub fn lifetime_cash(&self) -> Amount {
+        self.lifetime_cash
+    }
+
+    pub fn seniority(&self) -> u64 {
+        self.seniority
+    }
+
+    pub fn promotions(&self) -> u32 {
+        self.promotions
+    }
+
+    pub fn perks(&self) -> &HashSet<String> {
+        &self.perks
+    }
+
+    /// Count cash earned towards the next promotion
+    pub fn earn(&mut self, cash: impl Into<Amount>) {
+        self.lifetime_cash += cash.into();
+    }
+
+    /// Seniority that getting promoted now would give
+    pub fn pending(&self) -> u64 {
+        Self::points(self.lifetime_cash).saturating_sub(self.earned)
+    }
+
+    /// Hand out the pending seniority, returning how much that was
+    /// Only the career side of a promotion, see `GameState::promote`
+    pub(crate) fn promote(&mut self) -> u64 {
+        let points = self.pending();
+        self.seniority += points;
+        self.earned += points;
+        self.promotions += 1;
+        points
+    }
+
+    /// Perks that aren't owned yet and have everything they require
+    /// Sorted by ID
+    pub fn avail_perks(&self) -> Vec<Perk> {
+        let mut avail: Vec<Perk> = perks()
+            .iter()
+            .filter(|x| !self.perks.contains(&x.id))
+            .filter(|x| x.requires.iter().all(|req| self.perks.contains(req)))
+            .cloned()
+            .collect();
+        avail.sort_by(|a, b| a.id.cmp(&b.id));
+        avail
+    }
+
+    /// Spend seniority on a perk
+    pub fn buy_perk(&mut self, id: &str) -> Result<(), PromotionError> {
+        let perk = self
+            .avail_perks()
+            .into_iter()
+            .find(|x| x.id == id)
+            .ok_or(PromotionError::PerkUnavailable)?;
+        if self.seniority < perk.cost {
+            return Err(PromotionError::NotEnoughSeniority);
+        }
+        self.seniority -= perk.cost;
+        self.perks.insert(perk.id);
+        Ok(())
+    }
+
+    /// Effects of every owned perk, sorted by perk ID so multipliers are
+    /// always multiplied in the same order
+    /// Perks that aren't in the catalog any more don't count
+    pub fn effects(&self) -> Vec<&'static Effects> {
+        let by_id: HashMap<&str, &'static Perk> =
+            perks().iter().map(|x| (x.id.as_str(), x)).collect();
+        let mut owned: Vec<&'static Perk> = self
+            .perks
+            .iter()
+            .filter_map(|id| by_id.get(id.as_str()).copied())
+            .collect();
+        owned.sort_by(|a, b| a.id.cmp(&b.id));
+        owned.into_iter().flat_map(|x| &x.effects).collect()
+    }
+}
@@ -0,0 +1,96 @@
+#[cfg(test)]
+use rand::Rng;
+use rand::RngCore;
+use rand::rand_core::impls;
+use serde::{Deserialize, Serialize};
+
+#[test]
+fn same_seed() {
+    let mut a = GameRng::new(42);
+    let mut b = GameRng::new(42);
+    for _ in 0..100 {
+        assert_eq!(a.next_u64(), b.next_u64());
+    }
+}
+
+#[test]
+fn different_seed() {
+    let mut a = GameRng::new(1);
+    let mut b = GameRng::new(2);
+    assert!((0..10).any(|_| a.next_u64() != b.next_u64()));
+}
+
+#[test]
+fn keeps_seed() {
+    let mut rng = GameRng::new(1234);
+    let _ = rng.random_range(0..10);
+    assert_eq!(rng.seed(), 1234);
+}
+
+#[test]
+fn json_continues() {
+    let mut rng = GameRng::new(7);
+    for _ in 0..5 {
+        rng.next_u64();
+    }
+    let json = serde_json::to_string(&rng).unwrap();
+    let mut loaded: GameRng = serde_json::from_str(&json).unwrap();
+    assert_eq!(loaded, rng);
+    assert_eq!(loaded.next_u64(), rng.next_u64());
+}
+
+#[test]
+fn roughly_uniform() {
+    let mut rng = GameRng::new(99);
+    let mut counts = [0; 10];
+    for _ in 0..100_000 {
+        counts[rng.random_range(0..10)] += 1;
+    }
+    assert!(counts.iter().all(|&c| (9_000..11_000).contains(&c)));
+}
+
+/// Random number generator for everything random in the game
+/// It's SplitMix64, which is fast and simple, and its whole state is one
+/// number so it can go in the save file. The same seed always gives the same
+/// numbers, so a run (or a test) can be replayed exactly.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct GameRng {
+    /// What the generator started from
+    seed: u64,
+    /// Where the generator is now
+    state: u64,
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        Self { seed, state: seed }
+    }
+
+    /// Start from a random seed
+    pub fn from_entropy() -> Self {
+        Self::new(rand::random())
+    }
+
+    /// Returns the seed the generator started from
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+impl RngCore for GameRng {
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn fill_bytes(&mut self, dst: &mut [u8]) {
+        impls::fill_bytes_via_next(self, dst)
+    }
+}
@@ -0,0 +1,1178 @@
+#[cfg(test)]
+use crate::clock::*;
+use crate::currency::*;
+use crate::game::*;
+use crate::promotion::*;
+use crate::rng::*;
+#[cfg(test)]
+use crate::ticket::*;
+#[cfg(test)]
+use crate::upgrade::*;
+
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use chrono::{DateTime, Local};
+use flate2::Compression;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use serde::Serialize;
+use serde_json::Value;
+use std::cmp::Reverse;
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+#[cfg(test)]
+fn temp_save(name: &str) -> PathBuf {
+    std::env::temp_dir()
+        .join("ticket-tycoon-tests")
+        .join(format!("{}.json", name))
+}
+
+#[test]
+fn round_trip() {
+    let path = temp_save("round_trip");
+    let mut game = GameState::new();
+    while game.wallet().cash() < 40 {
+        game.init_queue();
+        for _ in 0..(Ticket::HARD_GOAL + 1) * 4 {
+            game.click_ticket(0);
+        }
+    }
+    assert!(
+        game.buy_upgrade(&"slightly_less_terrible_mouse".to_string())
+            .is_ok()
+    );
+    game.init_queue();
+    game.click_ticket(0);
+
+    assert!(save_to(&game, &path).is_ok());
+    let loaded = load_from(&path).unwrap();
+    assert_eq!(loaded.wallet(), game.wallet());
+    assert_eq!(loaded.working(), game.working());
+    assert_eq!(loaded.stats(), game.stats());
+    assert_eq!(loaded.avail_upgrades().len(), game.avail_upgrades().len());
+    let _ = fs::remove_file(path);
+}
+
+#[test]
+fn upgrades_not_saved() {
+    let game = GameState::new();
+    let json = serde_json::to_string(&game).unwrap();
+    assert!(!json.contains("ergonomic_mousepad"));
+}
+
+#[test]
+fn load_missing() {
+    let path = temp_save("does_not_exist");
+    assert!(load_from(&path).is_err_and(|x| matches!(x, SaveError::Io(_))));
+}
+
+#[test]
+fn load_garbage() {
+    let path = temp_save("garbage");
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(&path, "not a save").unwrap();
+    assert!(load_from(&path).is_err_and(|x| matches!(x, SaveError::Json(_))));
+    let _ = fs::remove_file(path);
+}
+
+#[test]
+fn envelope() {
+    let json = encode(&GameState::new()).unwrap();
+    let value: Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(value["version"], SAVE_VERSION);
+    assert!(value["game"].is_object());
+}
+
+#[test]
+fn migrations_cover_versions() {
+    assert_eq!(MIGRATIONS.len(), SAVE_VERSION as usize);
+}
+
+#[test]
+fn load_v0() {
+    let game = decode(include_str!("fixtures/save_v0.json")).unwrap();
+    assert_eq!(game.wallet().cash(), 123);
+    assert_eq!(game.wallet().xp(), 45);
+    assert_eq!(game.working().len(), 2);
+    assert_eq!(game.working()[0].clicked(), 2);
+    assert_eq!(game.purchased().len(), 6);
+    assert!(game.purchased().contains("extra_loud_mech_keyboard"));
+    assert!(game.purchased().contains("autosolve_easy_misc"));
+
+    let stats = game.stats();
+    assert!((stats.multiplier - 1.08 * 1.06 * 1.12 * 1.10 * 1.20).abs() < 0.0001);
+    assert_eq!(stats.cash_mult, 1.0);
+    assert_eq!(stats.xp_mult, 1.0);
+    assert_eq!(stats.autosolve.len(), 1);
+    assert!(
+        stats
+            .autosolve
+            .contains(&(Difficulty::Easy, Category::Misc))
+    );
+}
+
+#[test]
+fn load_v1() {
+    let game = decode(include_str!("fixtures/save_v1.json")).unwrap();
+    assert_eq!(game.wallet().cash(), 2010);
+    assert_eq!(game.wallet().xp(), 380);
+    assert_eq!(game.working().len(), 1);
+    assert_eq!(game.purchased().len(), 12);
+    assert!(game.purchased().contains("annual_performance_review"));
+
+    let stats = game.stats();
+    assert!((stats.multiplier - 1.08 * 1.06 * 1.12 * 1.10 * 1.20).abs() < 0.0001);
+    assert!((stats.cash_mult - 1.10).abs() < 0.0001);
+    assert_eq!(stats.xp_mult, 1.0);
+    assert_eq!(stats.autosolve.len(), 6);
+    assert!(
+        stats
+            .autosolve
+            .contains(&(Difficulty::Med, Category::Network))
+    );
+}
+
+#[test]
+fn load_v2() {
+    let mut game = decode(include_str!("fixtures/save_v2.json")).unwrap();
+    assert_eq!(game.wallet().cash(), 2510);
+    assert_eq!(game.wallet().xp(), 420);
+    assert_eq!(game.purchased().len(), 12);
+    assert_eq!(game.tickets_closed(), 87);
+
+    let stats = game.stats();
+    assert!((stats.cash_mult - 1.10).abs() < 0.0001);
+    assert_eq!(stats.autosolve.len(), 6);
+    let offline = game.catch_up(&ManualClock::new(1_700_000_060), 3600);
+    assert_eq!(offline.unwrap().secs, 60);
+}
+
+#[test]
+fn load_v3() {
+    let game = decode(include_str!("fixtures/save_v3.json")).unwrap();
+    assert_eq!(game.wallet().cash(), 1650);
+    assert_eq!(game.wallet().xp(), 310);
+    assert_eq!(game.purchased().len(), 9);
+    assert!(game.purchased().contains("ticket_auto_close_script"));
+    assert_eq!(game.tickets_closed(), 64);
+
+    let stats = game.stats();
+    assert!((stats.multiplier - 1.08 * 1.06 * 1.12 * 1.10 * 1.20).abs() < 0.0001);
+    // saved as 2, but autosolve_easy_windows was bought too
+    assert_eq!(stats.autosolve.len(), 3);
+    assert!(stats.autoclose);
+}
+
+#[test]
+fn load_old_no_autoclose() {
+    for json in [
+        include_str!("fixtures/save_v0.json"),
+        include_str!("fixtures/save_v1.json"),
+        include_str!("fixtures/save_v2.json"),
+    ] {
+        assert!(!decode(json).unwrap().stats().autoclose);
+    }
+}
+
+#[test]
+fn load_v4() {
+    let mut game = decode(include_str!("fixtures/save_v4.json")).unwrap();
+    assert_eq!(game.wallet().cash(), 1820);
+    assert_eq!(game.wallet().xp(), 335);
+    assert_eq!(game.purchased().len(), 9);
+    assert_eq!(game.tickets_closed(), 71);
+    assert_eq!(game.tickets_breached(), 5);
+    assert_eq!(game.backlog(), 1);
+    assert_eq!(game.working()[0].opened(), Some(1_700_000_000));
+    assert_eq!(game.working()[1].opened(), Some(1_699_999_400));
+    assert!(game.working()[1].breached(1_700_000_010));
+
+    let stats = game.stats();
+    assert_eq!(stats.autosolve.len(), 3);
+    assert!(stats.autoclose);
+    let offline = game.catch_up(&ManualClock::new(1_700_000_010), 3600);
+    assert_eq!(offline.unwrap().secs, 10);
+}
+
+#[test]
+fn load_old_slas() {
+    for json in [
+        include_str!("fixtures/save_v0.json"),
+        include_str!("fixtures/save_v1.json"),
+        include_str!("fixtures/save_v2.json"),
+        include_str!("fixtures/save_v3.json"),
+    ] {
+        let mut game = decode(json).unwrap();
+        assert_eq!(game.tickets_breached(), 0);
+        assert!(game.working().iter().all(|t| t.opened().is_none()));
+        game.tick(&ManualClock::new(1_700_000_100));
+        assert!(game.working().iter().all(|t| t.opened().is_some()));
+    }
+}
+
+#[test]
+fn load_v5() {
+    let game = decode(include_str!("fixtures/save_v5.json")).unwrap();
+    assert_eq!(game.wallet().cash(), 2240);
+    assert_eq!(game.wallet().xp(), 390);
+    assert_eq!(game.purchased().len(), 10);
+    assert!(game.purchased().contains("pager_duty_rotation"));
+    assert_eq!(game.tickets_closed(), 80);
+    assert_eq!(game.working()[0].priority(), &Priority::P1);
+    assert_eq!(game.working()[1].priority(), &Priority::P4);
+
+    let stats = game.stats();
+    assert_eq!(stats.autosolve.len(), 3);
+    assert!(stats.autoclose);
+    assert!((stats.priority_mult[&Priority::P1] - 1.25).abs() < 0.0001);
+}
+
+#[test]
+fn load_old_priorities() {
+    for json in [
+        include_str!("fixtures/save_v0.json"),
+        include_str!("fixtures/save_v1.json"),
+        include_str!("fixtures/save_v2.json"),
+        include_str!("fixtures/save_v3.json"),
+        include_str!("fixtures/save_v4.json"),
+    ] {
+        let game = decode(json).unwrap();
+        assert!(game.stats().priority_mult.is_empty());
+        assert!(game.working().iter().all(|t| t.priority() == &Priority::P3));
+    }
+}
+
+#[test]
+fn load_v6() {
+    let mut game = decode(include_str!("fixtures/save_v6.json")).unwrap();
+    assert_eq!(game.wallet().cash(), 3100);
+    assert_eq!(game.tickets_closed(), 95);
+    assert_eq!(game.seed(), 12345);
+
+    // the generator picks up where it was saved, so this always plays out the same
+    let mut again = decode(include_str!("fixtures/save_v6.json")).unwrap();
+    let clock = ManualClock::new(1_700_000_000);
+    for _ in 0..60 {
+        clock.advance(1);
+        game.tick(&clock);
+        again.tick(&clock);
+    }
+    assert_eq!(game.wallet(), again.wallet());
+    assert_eq!(game.working(), again.working());
+    assert_eq!(game.tickets_closed(), again.tickets_closed());
+}
+
+#[test]
+fn load_v7() {
+    let mut game = decode(include_str!("fixtures/save_v7.json")).unwrap();
+    assert_eq!(game.rounding(), Rounding::Carry);
+    assert_eq!(game.wallet().cash(), 3100);
+    // 0.6 clicks carried over plus about 1.69 from this click
+    game.click_ticket(1);
+    assert_eq!(game.working()[1].clicked(), 14);
+}
+
+#[test]
+fn load_v8() {
+    let game = decode(include_str!("fixtures/save_v8.json")).unwrap();
+    assert_eq!(game.wallet().cash(), 3100);
+    assert_eq!(game.modifiers(), &vec![Effects::IncCashMultiplier(1.5)]);
+    let stats = game.stats();
+    assert!((stats.multiplier - 1.08 * 1.06 * 1.12 * 1.10 * 1.20).abs() < 0.0001);
+    assert_eq!(stats.cash_mult, 1.5);
+    assert_eq!(stats.autosolve.len(), 3);
+    assert!(stats.autoclose);
+    assert!((stats.priority_mult[&Priority::P1] - 1.25).abs() < 0.0001);
+}
+
+#[test]
+fn load_v9() {
+    let game = decode(include_str!("fixtures/save_v9.json")).unwrap();
+    assert_eq!(game.level("unpaid_overtime"), 3);
+    assert_eq!(game.level("quarterly_bonus"), 1);
+    let overtime = &game.upgrades()["unpaid_overtime"];
+    assert_eq!(game.price(overtime), overtime.cost_at(3));
+    let stats = game.stats();
+    let clicks = 1.08 * 1.06 * 1.12 * 1.10 * 1.20 * 1.05f32.powi(3);
+    assert!((stats.multiplier - clicks).abs() < 0.0001);
+}
+
+#[test]
+fn load_v10() {
+    let game = decode(include_str!("fixtures/save_v10.json")).unwrap();
+    let career = game.career();
+    assert_eq!(career.promotions(), 2);
+    assert_eq!(career.seniority(), 1);
+    assert_eq!(career.pending(), 0);
+    assert!(career.perks().contains("mentor"));
+    assert!((game.stats().multiplier - 1.08 * 1.25).abs() < 0.0001);
+}
+
+#[test]
+fn load_v11() {
+    let game = decode(include_str!("fixtures/save_v11.json")).unwrap();
+    assert_eq!(game.reputation(), 42);
+    assert_eq!(game.wallet().cash(), 12);
+}
+
+#[test]
+fn load_v12() {
+    let game = decode(include_str!("fixtures/save_v12.json")).unwrap();
+    assert_eq!(game.arrival_rate(), 0.5);
+    assert_eq!(game.reputation(), 42);
+}
+
+#[test]
+fn load_old_arrival_rate() {
+    let game = decode(include_str!("fixtures/save_v11.json")).unwrap();
+    assert_eq!(game.arrival_rate(), 0.2);
+}
+
+#[test]
+fn load_old_reputation() {
+    let json = include_str!("fixtures/save_v10.json").replace(
+        "\"cash\": 12, \"xp\": 40",
+        "\"cash\": 12, \"xp\": 40, \"reputation\": 35",
+    );
+    let game = decode(&json).unwrap();
+    assert_eq!(game.reputation(), 35);
+    assert_eq!(
+        game.wallet(),
+        &Currency::new()
+            .with(Resource::Cash, 12)
+            .with(Resource::Xp, 40)
+    );
+    let game = decode(include_str!("fixtures/save_v10.json")).unwrap();
+    assert_eq!(game.reputation(), 0);
+}
+
+#[test]
+fn load_old_career() {
+    let game = decode(include_str!("fixtures/save_v9.json")).unwrap();
+    assert_eq!(game.career(), &Career::default());
+}
+
+#[test]
+fn load_old_levels() {
+    let game = decode(include_str!("fixtures/save_v8.json")).unwrap();
+    assert!(game.upgrades().values().all(|x| game.level(&x.id) <= 1));
+    assert_eq!(game.level("pager_duty_rotation"), 1);
+}
+
+#[test]
+fn load_repairs_stats() {
+    // a save from when a bad catalog had zeroed the click multiplier
+    let json = include_str!("fixtures/save_v7.json").replace("1.7", "0.0");
+    let game = decode(&json).unwrap();
+    assert!((game.stats().multiplier - 1.08 * 1.06 * 1.12 * 1.10 * 1.20).abs() < 0.0001);
+    assert!(game.modifiers().is_empty());
+}
+
+#[test]
+fn load_old_rounding() {
+    for json in [
+        include_str!("fixtures/save_v0.json"),
+        include_str!("fixtures/save_v5.json"),
+        include_str!("fixtures/save_v6.json"),
+    ] {
+        assert_eq!(decode(json).unwrap().rounding(), Rounding::Random);
+    }
+}
+
+#[test]
+fn load_old_rng() {
+    for json in [
+        include_str!("fixtures/save_v0.json"),
+        include_str!("fixtures/save_v4.json"),
+        include_str!("fixtures/save_v5.json"),
+    ] {
+        // each old save gets its own random seed
+        assert_ne!(decode(json).unwrap().seed(), decode(json).unwrap().seed());
+    }
+}
+
+#[test]
+fn load_old_no_offline() {
+    for json in [
+        include_str!("fixtures/save_v0.json"),
+        include_str!("fixtures/save_v1.json"),
+    ] {
+        let mut game = decode(json).unwrap();
+        assert_eq!(game.tickets_closed(), 0);
+        assert_eq!(game.catch_up(&ManualClock::new(1_700_000_000), 3600), None);
+    }
+}
+
+#[test]
+fn load_future_version() {
+    let json = format!("{{\"version\":{},\"game\":{{}}}}", SAVE_VERSION + 1);
+    assert!(decode(&json).is_err_and(|x| match x {
+        SaveError::UnknownVersion(v) => v == SAVE_VERSION + 1,
+        _ => false,
+    }));
+}
+
+#[test]
+fn load_huge_version() {
+    // would wrap around to version 1 if it was cut down to a u32
+    let json = format!("{{\"version\":{},\"game\":{{}}}}", (1u64 << 32) + 1);
+    assert!(decode(&json).is_err_and(|x| matches!(x, SaveError::UnknownVersion(_))));
+}
+
+#[test]
+fn no_temp_file_left() {
+    let path = temp_save("no_temp_file_left");
+    assert!(save_to(&GameState::new(), &path).is_ok());
+    assert!(path.exists());
+    assert!(!temp_path(&path).exists());
+    let _ = fs::remove_file(path);
+}
+
+#[test]
+fn overwrite_save() {
+    let path = temp_save("overwrite_save");
+    let mut game = GameState::new();
+    assert!(save_to(&game, &path).is_ok());
+    game.init_queue();
+    assert!(save_to(&game, &path).is_ok());
+    assert_eq!(load_from(&path).unwrap().working().len(), 4);
+    let _ = fs::remove_file(path);
+}
+
+#[test]
+fn autosave_interval() {
+    let start = Instant::now();
+    let mut autosave = Autosave::new(start);
+    assert!(!autosave.due(start));
+    assert!(!autosave.due(start + Autosave::INTERVAL - Duration::from_millis(1)));
+    assert!(autosave.due(start + Autosave::INTERVAL));
+    autosave.saved(start + Autosave::INTERVAL);
+    assert!(!autosave.due(start + Autosave::INTERVAL));
+}
+
+#[test]
+fn autosave_debounce() {
+    let start = Instant::now();
+    let mut autosave = Autosave::new(start);
+    autosave.request(start);
+    assert!(!autosave.due(start));
+    // more requests don't push the save back
+    autosave.request(start + Duration::from_millis(500));
+    assert!(autosave.due(start + Autosave::DEBOUNCE));
+    autosave.saved(start + Autosave::DEBOUNCE);
+    assert!(!autosave.due(start + Autosave::DEBOUNCE * 2));
+}
+
+#[cfg(test)]
+fn temp_slots(name: &str) -> Slots {
+    let dir = std::env::temp_dir().join("ticket-tycoon-tests").join(name);
+    let _ = fs::remove_dir_all(&dir);
+    Slots::new(dir)
+}
+
+#[test]
+fn slots_empty() {
+    let slots = temp_slots("slots_empty");
+    assert!(slots.list().unwrap().is_empty());
+}
+
+#[test]
+fn slots_create() {
+    let slots = temp_slots("slots_create");
+    assert!(slots.create("Ashton").is_ok());
+    assert!(
+        slots
+            .create("Ashton")
+            .is_err_and(|x| matches!(x, SaveError::SlotExists(_)))
+    );
+    let list = slots.list().unwrap();
+    assert_eq!(list.len(), 1);
+    assert_eq!(list[0].name, "Ashton");
+    assert_eq!(list[0].cash, 0);
+    assert_eq!(list[0].purchased, 0);
+    assert!(list[0].last_played.is_some());
+}
+
+#[test]
+fn slots_bad_names() {
+    let slots = temp_slots("slots_bad_names");
+    for name in ["", "   ", "../escape", "a/b", ".hidden"] {
+        assert!(
+            slots
+                .create(name)
+                .is_err_and(|x| matches!(x, SaveError::BadSlotName(_)))
+        );
+    }
+    assert!(slots.list().unwrap().is_empty());
+}
+
+#[test]
+fn slots_summary() {
+    let slots = temp_slots("slots_summary");
+    let game = decode(include_str!("fixtures/save_v1.json")).unwrap();
+    assert!(slots.save("late game", &game).is_ok());
+    let list = slots.list().unwrap();
+    assert_eq!(list[0].cash, 2010);
+    assert_eq!(list[0].xp, 380);
+    assert_eq!(list[0].purchased, 12);
+}
+
+#[test]
+fn slots_broken() {
+    let slots = temp_slots("slots_broken");
+    assert!(slots.create("fine").is_ok());
+    fs::write(slots.path("corrupt").unwrap(), "{ not a save").unwrap();
+    let list = slots.list().unwrap();
+    assert_eq!(list.len(), 2);
+    let corrupt = list.iter().find(|x| x.name == "corrupt").unwrap();
+    assert!(corrupt.broken);
+    assert!(!list.iter().find(|x| x.name == "fine").unwrap().broken);
+    assert!(slots.load("corrupt").is_err());
+}
+
+#[test]
+fn slots_rename() {
+    let slots = temp_slots("slots_rename");
+    assert!(slots.create("one").is_ok());
+    assert!(slots.create("two").is_ok());
+    assert!(
+        slots
+            .rename("one", "two")
+            .is_err_and(|x| matches!(x, SaveError::SlotExists(_)))
+    );
+    assert!(
+        slots
+            .rename("three", "four")
+            .is_err_and(|x| matches!(x, SaveError::NoSlot(_)))
+    );
+    assert!(slots.rename("one", "three").is_ok());
+    let mut names: Vec<_> = slots.list().unwrap().into_iter().map(|s| s.name).collect();
+    names.sort();
+    assert_eq!(names, vec!["three", "two"]);
+}
+
+#[test]
+fn slots_duplicate() {
+    let slots = temp_slots("slots_duplicate");
+    let game = decode(include_str!("fixtures/save_v1.json")).unwrap();
+    assert!(slots.save("main", &game).is_ok());
+    // last played a while ago, so a copy made now would look different
+    File::options()
+        .write(true)
+        .open(slots.path("main").unwrap())
+        .unwrap()
+        .set_modified(std::time::SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000))
+        .unwrap();
+    assert!(slots.duplicate("main", "copy").is_ok());
+    let copy = slots.load("copy").unwrap();
+    assert_eq!(copy.wallet(), game.wallet());
+    assert_eq!(copy.purchased(), game.purchased());
+    let list = slots.list().unwrap();
+    assert_eq!(list.len(), 2);
+    assert_eq!(list[0].last_played, list[1].last_played);
+}
+
+#[test]
+fn slots_delete() {
+    let slots = temp_slots("slots_delete");
+    assert!(slots.create("gone").is_ok());
+    assert!(slots.delete("gone").is_ok());
+    assert!(
+        slots
+            .delete("gone")
+            .is_err_and(|x| matches!(x, SaveError::NoSlot(_)))
+    );
+    assert!(slots.list().unwrap().is_empty());
+}
+
+#[test]
+fn export_round_trip() {
+    let game = decode(include_str!("fixtures/save_v1.json")).unwrap();
+    let text = export(&game).unwrap();
+    assert!(!text.contains(char::is_whitespace));
+    let imported = import(&format!("  {}\n", text)).unwrap();
+    assert_eq!(imported.wallet(), game.wallet());
+    assert_eq!(imported.purchased(), game.purchased());
+    assert_eq!(imported.working(), game.working());
+    assert_eq!(imported.stats(), game.stats());
+}
+
+#[test]
+fn export_compressed() {
+    let game = decode(include_str!("fixtures/save_v1.json")).unwrap();
+    let text = export(&game).unwrap();
+    let plain = checksummed(&encode(&game).unwrap());
+    // even a small save comes out at well under two thirds of the size
+    assert!(
+        text.len() * 3 < plain.len() * 2,
+        "{} vs {}",
+        text.len(),
+        plain.len()
+    );
+    // exports made before they were compressed still import
+    assert_eq!(import(&plain).unwrap().wallet(), game.wallet());
+}
+
+#[test]
+fn import_not_zlib() {
+    let text = checksummed("not zlib or JSON");
+    assert!(import(&text).is_err_and(|x| matches!(x, SaveError::BadExport)));
+}
+
+#[test]
+fn import_not_base64() {
+    assert!(import("not an export!").is_err_and(|x| matches!(x, SaveError::BadExport)));
+    assert!(import("").is_err_and(|x| matches!(x, SaveError::BadExport)));
+}
+
+#[test]
+fn import_tampered() {
+    let text = export(&GameState::new()).unwrap();
+    let mut bytes = URL_SAFE_NO_PAD.decode(&text).unwrap();
+    let last = bytes.len() - 2;
+    bytes[last] ^= 1;
+    let tampered = URL_SAFE_NO_PAD.encode(bytes);
+    assert!(import(&tampered).is_err_and(|x| matches!(x, SaveError::BadChecksum)));
+    assert!(import(&text[..text.len() - 4]).is_err());
+}
+
+#[test]
+fn import_old_version() {
+    let text = checksummed(include_str!("fixtures/save_v0.json"));
+    let game = import(&text).unwrap();
+    assert_eq!(game.wallet().cash(), 123);
+    assert_eq!(game.purchased().len(), 6);
+}
+
+#[test]
+fn import_future_version() {
+    let text = checksummed(&format!(
+        "{{\"version\":{},\"game\":{{}}}}",
+        SAVE_VERSION + 1
+    ));
+    assert!(import(&text).is_err_and(|x| matches!(x, SaveError::UnknownVersion(_))));
+}
+
+#[test]
+fn import_unknown_upgrade() {
+    let json = include_str!("fixtures/save_v1.json")
+        .replace("\"annual_performance_review\"", "\"removed_upgrade\"");
+    let text = checksummed(&json);
+    assert!(import(&text).is_err_and(|x| match x {
+        SaveError::UnknownUpgrades(ids) => ids == vec!["removed_upgrade".to_string()],
+        _ => false,
+    }));
+}
+
+/// Current version of the save format
+/// Bump this and add a migration whenever the saved fields of GameState change
+pub const SAVE_VERSION: u32 = 12;
+
+/// `MIGRATIONS[n]` upgrades the game data of a version `n` save to version
+/// `n + 1`, so old saves get walked forward one version at a time
+const MIGRATIONS: [fn(Value) -> Value; SAVE_VERSION as usize] = [
+    v0_to_v1, v1_to_v2, v2_to_v3, v3_to_v4, v4_to_v5, v5_to_v6, v6_to_v7, v7_to_v8, v8_to_v9,
+    v9_to_v10, v10_to_v11, v11_to_v12,
+];
+
+/// Version 0 saves were a bare GameState without the version envelope
+/// The fields themselves didn't change
+fn v0_to_v1(game: Value) -> Value {
+    game
+}
+
+/// Version 2 started counting closed tickets and remembering the last tick for
+/// offline progress
+/// Older saves have no idea when they were last played, so they don't get any
+fn v1_to_v2(mut game: Value) -> Value {
+    if let Value::Object(fields) = &mut game {
+        fields.insert("tickets_closed".to_string(), Value::from(0));
+        fields.insert("last_tick".to_string(), Value::Null);
+    }
+    game
+}
+
+/// Version 3 added the AutoClose upgrade effect, which nobody could have yet
+fn v2_to_v3(mut game: Value) -> Value {
+    if let Value::Object(fields) = &mut game {
+        fields.insert("autoclose".to_string(), Value::Bool(false));
+    }
+    game
+}
+
+/// Version 4 added SLAs
+/// Old tickets haven't started their SLA timer, that happens on the next tick
+fn v3_to_v4(mut game: Value) -> Value {
+    if let Value::Object(fields) = &mut game {
+        fields.insert("tickets_breached".to_string(), Value::from(0));
+        for list in ["queue", "working"] {
+            if let Some(Value::Array(tickets)) = fields.get_mut(list) {
+                for ticket in tickets.iter_mut().filter_map(Value::as_object_mut) {
+                    ticket.insert("opened".to_string(), Value::Null);
+                }
+            }
+        }
+    }
+    game
+}
+
+/// Version 5 added ticket priorities, old tickets are all normal (P3)
+fn v4_to_v5(mut game: Value) -> Value {
+    if let Value::Object(fields) = &mut game {
+        fields.insert(
+            "priority_mult".to_string(),
+            Value::Object(Default::default()),
+        );
+        for list in ["queue", "working"] {
+            if let Some(Value::Array(tickets)) = fields.get_mut(list) {
+                for ticket in tickets.iter_mut().filter_map(Value::as_object_mut) {
+                    ticket.insert("priority".to_string(), Value::from("P3"));
+                }
+            }
+        }
+    }
+    game
+}
+
+/// Version 6 saved the random number generator, old saves get a fresh one
+fn v5_to_v6(mut game: Value) -> Value {
+    if let Value::Object(fields) = &mut game {
+        let rng = serde_json::to_value(GameRng::from_entropy()).unwrap_or_default();
+        fields.insert("rng".to_string(), rng);
+    }
+    game
+}
+
+/// Version 7 added a choice of rounding, old saves keep rounding at random
+fn v6_to_v7(mut game: Value) -> Value {
+    if let Value::Object(fields) = &mut game {
+        fields.insert("rounding".to_string(), Value::from("Random"));
+        let remainders = serde_json::to_value(Remainders::default()).unwrap_or_default();
+        fields.insert("remainders".to_string(), remainders);
+    }
+    game
+}
+
+/// Version 8 stopped saving stats, they're worked out from the purchased
+/// upgrades instead, which also fixes any that had gone wrong
+fn v7_to_v8(mut game: Value) -> Value {
+    if let Value::Object(fields) = &mut game {
+        for stat in [
+            "multiplier",
+            "cash_mult",
+            "xp_mult",
+            "autosolve",
+            "autoclose",
+            "priority_mult",
+        ] {
+            fields.remove(stat);
+        }
+        fields.insert("modifiers".to_string(), Value::Array(Vec::new()));
+    }
+    game
+}
+
+/// Version 9 added repeatable upgrades, which nobody could have bought yet
+fn v8_to_v9(mut game: Value) -> Value {
+    if let Value::Object(fields) = &mut game {
+        fields.insert("levels".to_string(), Value::Object(Default::default()));
+    }
+    game
+}
+
+/// Version 10 added promotions, old saves start their career now
+fn v9_to_v10(mut game: Value) -> Value {
+    if let Value::Object(fields) = &mut game {
+        let career = serde_json::to_value(Career::default()).unwrap_or_default();
+        fields.insert("career".to_string(), career);
+    }
+    game
+}
+
+/// Version 11 moved reputation out of the wallet, since it isn't spent
+/// Version 10 games could already have some there
+fn v10_to_v11(mut game: Value) -> Value {
+    if let Value::Object(fields) = &mut game {
+        let reputation = fields
+            .get_mut("wallet")
+            .and_then(Value::as_object_mut)
+            .and_then(|wallet| wallet.remove("reputation"))
+            .unwrap_or(Value::from(0));
+        fields.insert("reputation".to_string(), reputation);
+    }
+    game
+}
+
+/// Version 12 made the ticket arrival rate a setting, old saves keep the rate
+/// they were played with
+fn v11_to_v12(mut game: Value) -> Value {
+    if let Value::Object(fields) = &mut game {
+        fields.insert("arrival_rate".to_string(), Value::from(0.2));
+    }
+    game
+}
+
+/// What actually gets written to disk
+#[derive(Serialize)]
+struct SaveFile<'a> {
+    version: u32,
+    game: &'a GameState,
+}
+
+#[derive(Debug)]
+pub enum SaveError {
+    /// Couldn't find a directory to put the save in
+    NoDataDir,
+    /// Save was written by a newer version of the game
+    UnknownVersion(u32),
+    /// Slot names are shown to the player and used as file names
+    BadSlotName(String),
+    SlotExists(String),
+    NoSlot(String),
+    /// Export string isn't valid base64 or is too short to hold a checksum
+    BadExport,
+    /// Export string was changed or cut off after it was made
+    BadChecksum,
+    /// Save bought upgrades that aren't in the catalog anymore
+    UnknownUpgrades(Vec<String>),
+    Io(std::io::Error),
+    Json(serde_json::Error),
+}
+
+impl From<std::io::Error> for SaveError {
+    fn from(err: std::io::Error) -> Self {
+        SaveError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for SaveError {
+    fn from(err: serde_json::Error) -> Self {
+        SaveError::Json(err)
+    }
+}
+
+/// Write the game to `path`, creating any missing directories
+/// The save is written to a temporary file first and then renamed over the
+/// old one, so a crash partway through never leaves a half-written save
+pub fn save_to(game: &GameState, path: &Path) -> Result<(), SaveError> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let tmp = temp_path(path);
+    let mut file = File::create(&tmp)?;
+    file.write_all(encode(game)?.as_bytes())?;
+    file.sync_all()?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+/// Where a save is written before it replaces `path`
+fn temp_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".tmp");
+    path.with_file_name(name)
+}
+
+/// Read a game from `path`
+pub fn load_from(path: &Path) -> Result<GameState, SaveError> {
+    decode(&fs::read_to_string(path)?)
+}
+
+/// Serialize the game as the current version of the save format
+pub fn encode(game: &GameState) -> Result<String, SaveError> {
+    let file = SaveFile {
+        version: SAVE_VERSION,
+        game,
+    };
+    Ok(serde_json::to_string(&file)?)
+}
+
+/// Parse a save of any known version, migrating it up to the current one
+/// The upgrade catalog is reloaded from upgrades.json rather than the save
+pub fn decode(json: &str) -> Result<GameState, SaveError> {
+    let mut save: Value = serde_json::from_str(json)?;
+    let (version, mut game) = match save.get("version").and_then(Value::as_u64) {
+        Some(v) => {
+            // too big for a u32 is still newer than anything this game knows
+            let v = u32::try_from(v).map_err(|_| SaveError::UnknownVersion(u32::MAX))?;
+            (v, save["game"].take())
+        }
+        None => (0, save),
+    };
+    if version > SAVE_VERSION {
+        return Err(SaveError::UnknownVersion(version));
+    }
+
+    for migration in &MIGRATIONS[version as usize..] {
+        game = migration(game);
+    }
+    Ok(serde_json::from_value(game)?)
+}
+
+/// Encode the game as a single line of text that can be pasted elsewhere
+/// It's the save JSON compressed with zlib, behind a CRC32 checksum, all in
+/// URL-safe base64
+pub fn export(game: &GameState) -> Result<String, SaveError> {
+    let mut zlib = ZlibEncoder::new(Vec::new(), Compression::best());
+    zlib.write_all(encode(game)?.as_bytes())?;
+    Ok(checksummed(&zlib.finish()?))
+}
+
+/// Put a checksum in front of the data and base64 the whole thing
+fn checksummed(data: &(impl AsRef<[u8]> + ?Sized)) -> String {
+    let data = data.as_ref();
+    let mut bytes = crc32fast::hash(data).to_be_bytes().to_vec();
+    bytes.extend_from_slice(data);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// Decode a game made by `export()`
+/// Will return an error if:
+/// - The checksum doesn't match
+/// - The save is from a newer version of the game
+/// - The save has purchased upgrades that don't exist
+pub fn import(text: &str) -> Result<GameState, SaveError> {
+    let bytes = URL_SAFE_NO_PAD
+        .decode(text.trim())
+        .map_err(|_| SaveError::BadExport)?;
+    if bytes.len() < 4 {
+        return Err(SaveError::BadExport);
+    }
+    let (checksum, data) = bytes.split_at(4);
+    if crc32fast::hash(data).to_be_bytes() != checksum {
+        return Err(SaveError::BadChecksum);
+    }
+    // exports from before compression are the JSON itself, which can't be
+    // mistaken for zlib since that always starts with 0x78
+    let mut json = String::new();
+    if data.starts_with(b"{") {
+        json = String::from_utf8(data.to_vec()).map_err(|_| SaveError::BadExport)?;
+    } else {
+        ZlibDecoder::new(data)
+            .read_to_string(&mut json)
+            .map_err(|_| SaveError::BadExport)?;
+    }
+
+    let game = decode(&json)?;
+    let mut unknown: Vec<String> = game
+        .purchased()
+        .iter()
+        .filter(|id| !game.upgrades().contains_key(*id))
+        .cloned()
+        .collect();
+    if !unknown.is_empty() {
+        unknown.sort();
+        return Err(SaveError::UnknownUpgrades(unknown));
+    }
+    Ok(game)
+}
+
+/// Decides when the game should be written to disk
+/// Saves happen every `INTERVAL` no matter what, and `DEBOUNCE` after an
+/// important event so that a burst of events only causes one save
+#[derive(Debug)]
+pub struct Autosave {
+    /// When the game was last saved
+    last_save: Instant,
+    /// When the oldest unsaved event happened
+    requested: Option<Instant>,
+}
+
+impl Autosave {
+    pub const INTERVAL: Duration = Duration::from_secs(5);
+    pub const DEBOUNCE: Duration = Duration::from_secs(1);
+
+    pub fn new(now: Instant) -> Self {
+        Self {
+            last_save: now,
+            requested: None,
+        }
+    }
+
+    /// Something happened that should be saved soon
+    pub fn request(&mut self, now: Instant) {
+        self.requested.get_or_insert(now);
+    }
+
+    /// Check if it's time to save
+    pub fn due(&self, now: Instant) -> bool {
+        let requested = self
+            .requested
+            .is_some_and(|at| now.duration_since(at) >= Self::DEBOUNCE);
+        requested || now.duration_since(self.last_save) >= Self::INTERVAL
+    }
+
+    /// Record that the game was just saved
+    pub fn saved(&mut self, now: Instant) {
+        self.last_save = now;
+        self.requested = None;
+    }
+}
+
+/// Summary of a save slot for the slot picker
+#[derive(Clone, Debug, PartialEq)]
+pub struct SlotInfo {
+    pub name: String,
+    pub cash: Amount,
+    pub xp: Amount,
+    /// How many upgrades have been bought
+    pub purchased: usize,
+    /// When the slot was last saved
+    pub last_played: Option<DateTime<Local>>,
+    /// The save couldn't be loaded (corrupt, unreadable or from a newer
+    /// version), so the numbers above are all 0
+    pub broken: bool,
+}
+
+/// Named save slots, each one a save file in the same directory
+#[derive(Clone, Debug, PartialEq)]
+pub struct Slots {
+    dir: PathBuf,
+}
+
+impl Slots {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// Slots in the user's data directory
+    /// A save from before slots existed is moved into a "Default" slot
+    pub fn open() -> Result<Self, SaveError> {
+        let root = dirs::data_dir()
+            .ok_or(SaveError::NoDataDir)?
+            .join("ticket-tycoon");
+        let slots = Self::new(root.join("slots"));
+
+        let legacy = root.join("save.json");
+        if legacy.exists() {
+            fs::create_dir_all(&slots.dir)?;
+            let default = slots.path("Default")?;
+            if !default.exists() {
+                fs::rename(&legacy, default)?;
+            }
+        }
+        Ok(slots)
+    }
+
+    /// Where the save for a slot lives
+    fn path(&self, name: &str) -> Result<PathBuf, SaveError> {
+        let valid = name == name.trim()
+            && !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_alphanumeric() || " -_".contains(c));
+        if !valid {
+            return Err(SaveError::BadSlotName(name.to_string()));
+        }
+        Ok(self.dir.join(format!("{}.json", name)))
+    }
+
+    /// Path of a slot that has to exist already
+    fn existing(&self, name: &str) -> Result<PathBuf, SaveError> {
+        let path = self.path(name)?;
+        if !path.exists() {
+            return Err(SaveError::NoSlot(name.to_string()));
+        }
+        Ok(path)
+    }
+
+    /// Path of a slot that must not exist yet
+    fn unused(&self, name: &str) -> Result<PathBuf, SaveError> {
+        let path = self.path(name)?;
+        if path.exists() {
+            return Err(SaveError::SlotExists(name.to_string()));
+        }
+        Ok(path)
+    }
+
+    /// Summaries of every slot, most recently played first
+    /// Slots that can't be loaded are still listed, marked as broken
+    pub fn list(&self) -> Result<Vec<SlotInfo>, SaveError> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut slots = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            let Some(name) = path.file_stem().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            let (cash, xp, purchased, broken) = match load_from(&path) {
+                Ok(game) => (
+                    game.wallet().cash(),
+                    game.wallet().xp(),
+                    game.purchased().len(),
+                    false,
+                ),
+                Err(_) => (Amount::ZERO, Amount::ZERO, 0, true),
+            };
+            let last_played = fs::metadata(&path)
+                .and_then(|m| m.modified())
+                .ok()
+                .map(DateTime::<Local>::from);
+            slots.push(SlotInfo {
+                name: name.to_string(),
+                cash,
+                xp,
+                purchased,
+                last_played,
+                broken,
+            });
+        }
+        slots.sort_by_key(|slot| Reverse(slot.last_played));
+        Ok(slots)
+    }
+
+    /// Start a new game in an unused slot, with a few tickets to work on
+    pub fn create(&self, name: &str) -> Result<GameState, SaveError> {
+        let path = self.unused(name)?;
+        let mut game = GameState::new();
+        game.init_queue();
+        save_to(&game, &path)?;
+        Ok(game)
+    }
+
+    pub fn load(&self, name: &str) -> Result<GameState, SaveError> {
+        load_from(&self.existing(name)?)
+    }
+
+    /// Write the game to a slot, replacing whatever was there
+    pub fn save(&self, name: &str, game: &GameState) -> Result<(), SaveError> {
+        save_to(game, &self.path(name)?)
+    }
+
+    pub fn rename(&self, from: &str, to: &str) -> Result<(), SaveError> {
+        let from = self.existing(from)?;
+        fs::rename(from, self.unused(to)?)?;
+        Ok(())
+    }
+
+    /// Copy a slot so the run can branch off
+    /// The copy keeps the original's modification time, so it shows the same
+    /// "last played" instead of when it was copied
+    pub fn duplicate(&self, from: &str, to: &str) -> Result<(), SaveError> {
+        let from = self.existing(from)?;
+        let to = self.unused(to)?;
+        fs::copy(&from, &to)?;
+        let modified = fs::metadata(from)?.modified()?;
+        File::options()
+            .write(true)
+            .open(to)?
+            .set_modified(modified)?;
+        Ok(())
+    }
+
+    pub fn delete(&self, name: &str) -> Result<(), SaveError> {
+        fs::remove_file(self.existing(name)?)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,251 @@
+use crate::command::*;
+use crate::currency::*;
+use crate::game::*;
+use crate::rng::*;
+use crate::upgrade::*;
+
+use rand::Rng;
+
+#[cfg(test)]
+fn buys(commands: &[Command]) -> Vec<&String> {
+    commands
+        .iter()
+        .filter_map(|x| match x {
+            Command::BuyUpgrade(id) => Some(id),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn clicks_per_second() {
+    let mut game = GameState::with_seed(1);
+    game.init_queue();
+    let clicks = |x: Vec<Command>| {
+        x.iter()
+            .filter(|c| matches!(c, Command::ClickTicket(_)))
+            .count()
+    };
+    assert_eq!(clicks(Greedy::new(3).act(&game)), 3);
+    assert_eq!(clicks(Roi::new(2).act(&game)), 2);
+    assert_eq!(clicks(RandomClicker::new(4, 0).act(&game)), 4);
+}
+
+#[test]
+fn no_clicks_without_tickets() {
+    let game = GameState::with_seed(1);
+    assert!(Greedy::new(3).act(&game).is_empty());
+    assert!(RandomClicker::new(3, 0).act(&game).is_empty());
+}
+
+#[test]
+fn random_clicks_in_range() {
+    let mut game = GameState::with_seed(1);
+    game.init_queue();
+    let mut strategy = RandomClicker::new(100, 0);
+    let n = game.working().len();
+    for command in strategy.act(&game) {
+        if let Command::ClickTicket(i) = command {
+            assert!(i < n);
+        }
+    }
+}
+
+#[test]
+fn greedy_buys_cheapest() {
+    let mut game = GameState::with_seed(1);
+    game.init_queue();
+    // can't afford anything yet
+    assert!(buys(&Greedy::new(1).act(&game)).is_empty());
+    while game.wallet().xp() < 100 {
+        let _ = game.apply(Command::ClickTicket(0));
+        game.step(1);
+    }
+    let cheapest = game
+        .avail_upgrades()
+        .into_iter()
+        .min_by_key(|x| (cost(&game, x), x.id.clone()))
+        .unwrap();
+    assert_eq!(buys(&Greedy::new(1).act(&game)), vec![&cheapest.id]);
+}
+
+#[cfg(test)]
+fn price(cash: u64) -> Currency {
+    let mut cost = Currency::new();
+    cost.add_cash(cash);
+    cost
+}
+
+#[test]
+fn roi_prefers_value() {
+    let cheap = Upgrade {
+        id: "a".to_string(),
+        name: String::new(),
+        desc: String::new(),
+        cost: price(10),
+        requires: Vec::new(),
+        effects: vec![Effects::IncMultiplier(1.01)],
+        repeat: None,
+        reputation: None,
+    };
+    let good = Upgrade {
+        id: "b".to_string(),
+        cost: price(20),
+        effects: vec![Effects::IncMultiplier(1.5)],
+        ..cheap.clone()
+    };
+    let game = GameState::new();
+    assert!(roi(&game, &good) > roi(&game, &cheap));
+}
+
+/// An automated player
+/// Each second of game time it looks at the game and decides what to do
+pub trait Strategy {
+    /// What to do this second
+    fn act(&mut self, game: &GameState) -> Vec<Command>;
+}
+
+/// Total price of the next level of an upgrade, with every resource counted
+/// the same
+pub fn cost(game: &GameState, upgrade: &Upgrade) -> Amount {
+    game.price(upgrade)
+        .iter()
+        .fold(Amount::ZERO, |total, (_, n)| total + n)
+}
+
+/// Whether the player has enough to buy the upgrade right now
+pub fn affordable(game: &GameState, upgrade: &Upgrade) -> bool {
+    game.wallet().missing(&game.price(upgrade)).is_none()
+}
+
+/// Rough guess at how much an effect helps
+fn value(effect: &Effects) -> f64 {
+    match effect {
+        Effects::IncMultiplier(x) | Effects::IncCashMultiplier(x) | Effects::IncXPMultiplier(x) => {
+            *x as f64 - 1.0
+        }
+        // only about a quarter of tickets have any one priority
+        Effects::IncPriorityMultiplier(_, x) => (*x as f64 - 1.0) / 4.0,
+        // about 1 in 15 tickets is any one difficulty + category
+        Effects::AutoSolve(..) => 0.1,
+        Effects::AutoClose => 0.2,
+    }
+}
+
+/// How much an upgrade helps for what it costs
+pub fn roi(game: &GameState, upgrade: &Upgrade) -> f64 {
+    upgrade.effects.iter().map(value).sum::<f64>() / cost(game, upgrade).as_f64().max(1.0)
+}
+
+/// Available upgrades sorted by ID, so choices don't depend on hash map order
+fn sorted_upgrades(game: &GameState) -> Vec<Upgrade> {
+    let mut avail = game.avail_upgrades();
+    avail.sort_by(|a, b| a.id.cmp(&b.id));
+    avail
+}
+
+/// Click the first ticket `clicks` times, if there is one
+fn click_first(game: &GameState, clicks: u32) -> Vec<Command> {
+    if game.working().is_empty() {
+        return Vec::new();
+    }
+    (0..clicks).map(|_| Command::ClickTicket(0)).collect()
+}
+
+/// Buy `target` if it can be afforded, otherwise keep saving up for it
+fn buy(game: &GameState, target: Option<Upgrade>, commands: &mut Vec<Command>) {
+    if let Some(upgrade) = target.filter(|x| affordable(game, x)) {
+        commands.push(Command::BuyUpgrade(upgrade.id));
+    }
+}
+
+/// Clicks random tickets and buys random upgrades it can afford
+pub struct RandomClicker {
+    clicks: u32,
+    rng: GameRng,
+}
+
+impl RandomClicker {
+    pub fn new(clicks: u32, seed: u64) -> Self {
+        Self {
+            clicks,
+            rng: GameRng::new(seed),
+        }
+    }
+}
+
+impl Strategy for RandomClicker {
+    fn act(&mut self, game: &GameState) -> Vec<Command> {
+        let mut commands = Vec::new();
+        let n = game.working().len();
+        if n > 0 {
+            for _ in 0..self.clicks {
+                commands.push(Command::ClickTicket(self.rng.random_range(0..n)));
+            }
+        }
+        let avail: Vec<Upgrade> = sorted_upgrades(game)
+            .into_iter()
+            .filter(|x| affordable(game, x))
+            .collect();
+        if !avail.is_empty() {
+            let i = self.rng.random_range(0..avail.len());
+            commands.push(Command::BuyUpgrade(avail[i].id.clone()));
+        }
+        commands
+    }
+}
+
+/// Clicks the first ticket and saves up for the cheapest upgrade
+pub struct Greedy {
+    clicks: u32,
+}
+
+impl Greedy {
+    pub fn new(clicks: u32) -> Self {
+        Self { clicks }
+    }
+}
+
+impl Strategy for Greedy {
+    fn act(&mut self, game: &GameState) -> Vec<Command> {
+        let mut commands = click_first(game, self.clicks);
+        let target = sorted_upgrades(game)
+            .into_iter()
+            .min_by_key(|x| cost(game, x));
+        buy(game, target, &mut commands);
+        commands
+    }
+}
+
+/// Clicks the first ticket and saves up for the upgrade with the best `roi`
+pub struct Roi {
+    clicks: u32,
+}
+
+impl Roi {
+    pub fn new(clicks: u32) -> Self {
+        Self { clicks }
+    }
+}
+
+impl Strategy for Roi {
+    fn act(&mut self, game: &GameState) -> Vec<Command> {
+        let mut commands = click_first(game, self.clicks);
+        let target = sorted_upgrades(game)
+            .into_iter()
+            .max_by(|a, b| roi(game, a).total_cmp(&roi(game, b)));
+        buy(game, target, &mut commands);
+        commands
+    }
+}
+
+/// Let `strategy` play `game` for `secs` seconds of game time
+/// Commands that fail (e.g. clicking a ticket that was just closed) are skipped
+pub fn play(game: &mut GameState, strategy: &mut dyn Strategy, secs: i64) {
+    for _ in 0..secs {
+        for command in strategy.act(game) {
+            let _ = game.apply(command);
+        }
+        game.step(1);
+    }
+}
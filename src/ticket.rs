@@ -87,6 +87,63 @@ fn equality() {
     );
 }
 
+#[test]
+fn default_priority() {
+    let mut ticket = Ticket::new(Difficulty::Easy, Category::Web, "name");
+    assert_eq!(ticket.priority(), &Priority::P3);
+    ticket.set_priority(Priority::P1);
+    assert_eq!(ticket.priority(), &Priority::P1);
+}
+
+#[test]
+fn priority_order() {
+    let mut priorities = vec![Priority::P3, Priority::P1, Priority::P4, Priority::P2];
+    priorities.sort();
+    assert_eq!(
+        priorities,
+        vec![Priority::P1, Priority::P2, Priority::P3, Priority::P4]
+    );
+}
+
+#[test]
+fn no_sla_until_opened() {
+    let ticket = Ticket::new(Difficulty::Easy, Category::Web, "name");
+    assert_eq!(ticket.opened(), None);
+    assert_eq!(ticket.deadline(), None);
+    assert!(!ticket.breached(i64::MAX));
+}
+
+#[test]
+fn sla_deadlines() {
+    for (diff, sla) in [
+        (Difficulty::Easy, Ticket::EASY_SLA),
+        (Difficulty::Med, Ticket::MED_SLA),
+        (Difficulty::Hard, Ticket::HARD_SLA),
+    ] {
+        let mut ticket = Ticket::new(diff, Category::Web, "name");
+        ticket.open(100);
+        assert_eq!(ticket.deadline(), Some(100 + sla));
+        assert!(!ticket.breached(100 + sla));
+        assert!(ticket.breached(100 + sla + 1));
+    }
+}
+
+#[test]
+fn open_once() {
+    let mut ticket = Ticket::new(Difficulty::Easy, Category::Web, "name");
+    ticket.open(100);
+    ticket.open(200);
+    assert_eq!(ticket.opened(), Some(100));
+}
+
+#[test]
+fn json_round_trip() {
+    let ticket = Ticket::new(Difficulty::Med, Category::Linux, "name");
+    let json = serde_json::to_string(&ticket).unwrap();
+    let parsed: Ticket = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed, ticket);
+}
+
 /// How difficult a ticket is to complete
 #[derive(Debug, Eq, Hash, PartialEq, Clone, Copy, Deserialize, Serialize)]
 pub enum Difficulty {
@@ -105,31 +162,50 @@ pub enum Category {
     Misc,
 }
 
+/// How urgent a ticket is, P1 is the most urgent
+/// Ordered so that sorting puts the most urgent tickets first
+#[derive(Debug, Eq, Hash, PartialEq, Ord, PartialOrd, Clone, Copy, Deserialize, Serialize)]
+pub enum Priority {
+    P1,
+    P2,
+    P3,
+    P4,
+}
+
 /// Ticket object
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
 pub struct Ticket {
     /// How hard the ticket is to complete
     difficulty: Difficulty,
     /// What kind of ticket it is
     category: Category,
+    /// How urgent the ticket is
+    priority: Priority,
     /// How many times the ticket has already been clicked
     clicked: u16,
     /// Name of the ticket
     name: String,
     /// How many clicks to complete
     goal: u16,
+    /// Unix timestamp of when the SLA timer started, if it has
+    opened: Option<i64>,
 }
 
 impl Ticket {
     pub const EASY_GOAL: u16 = 5;
     pub const MED_GOAL: u16 = 15;
     pub const HARD_GOAL: u16 = 30;
+    /// Seconds from when a ticket comes in until it breaches its SLA
+    pub const EASY_SLA: i64 = 2 * 60;
+    pub const MED_SLA: i64 = 5 * 60;
+    pub const HARD_SLA: i64 = 10 * 60;
 
-    /// Create a new ticket
+    /// Create a new ticket with normal (P3) priority
     pub fn new(difficulty: Difficulty, category: Category, name: &str) -> Self {
         Self {
             difficulty,
             category,
+            priority: Priority::P3,
             clicked: 0,
             name: name.to_string(),
             goal: match difficulty {
@@ -137,6 +213,7 @@ impl Ticket {
                 Difficulty::Med => Self::MED_GOAL,
                 Difficulty::Hard => Self::HARD_GOAL,
             },
+            opened: None,
         }
     }
 
@@ -150,6 +227,16 @@ impl Ticket {
         &self.category
     }
 
+    /// Returns a reference to the priority
+    pub fn priority(&self) -> &Priority {
+        &self.priority
+    }
+
+    /// Change how urgent the ticket is
+    pub fn set_priority(&mut self, priority: Priority) {
+        self.priority = priority;
+    }
+
     pub fn name(&self) -> &String {
         &self.name
     }
@@ -168,6 +255,32 @@ impl Ticket {
         self.clicked += i;
     }
 
+    /// Start the SLA timer, unless it's already running
+    pub fn open(&mut self, now: i64) {
+        self.opened.get_or_insert(now);
+    }
+
+    /// When the SLA timer started
+    pub fn opened(&self) -> Option<i64> {
+        self.opened
+    }
+
+    /// When the ticket breaches its SLA
+    /// The higher the difficulty, the longer the SLA
+    pub fn deadline(&self) -> Option<i64> {
+        let sla = match self.difficulty {
+            Difficulty::Easy => Self::EASY_SLA,
+            Difficulty::Med => Self::MED_SLA,
+            Difficulty::Hard => Self::HARD_SLA,
+        };
+        self.opened.map(|opened| opened + sla)
+    }
+
+    /// Check if the ticket is past its deadline
+    pub fn breached(&self, now: i64) -> bool {
+        self.deadline().is_some_and(|deadline| now > deadline)
+    }
+
     /// Check if a ticket has been clicked enough to be completed
     /// The higher the difficulty, the more the ticket needs to be clicked
     pub fn is_complete(&self) -> bool {
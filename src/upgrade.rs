@@ -1,5 +1,5 @@
 use crate::currency::*;
-use crate::ticket::{Category, Difficulty};
+use crate::ticket::{Category, Difficulty, Priority};
 
 use serde::{Deserialize, Serialize};
 
@@ -12,6 +12,8 @@ fn to_json() {
         cost: Currency::new(),
         requires: Vec::new(),
         effects: vec![Effects::IncMultiplier(0.2)],
+        repeat: None,
+        reputation: None,
     };
     let upgrade2 = Upgrade {
         id: "test-upgrade".to_string(),
@@ -23,19 +25,37 @@ fn to_json() {
             Effects::IncMultiplier(0.2),
             Effects::AutoSolve(Difficulty::Easy, Category::Misc),
         ],
+        repeat: None,
+        reputation: None,
     };
     let json1 = serde_json::to_string(&upgrade1).unwrap();
     let json2 = serde_json::to_string(&upgrade2).unwrap();
     assert_eq!(
         json1,
-        "{\"id\":\"test-upgrade\",\"name\":\"test upgrade\",\"desc\":\"This is just a test\",\"cost\":{\"cash\":0,\"xp\":0},\"requires\":[],\"effects\":[{\"IncMultiplier\":0.2}]}"
+        "{\"id\":\"test-upgrade\",\"name\":\"test upgrade\",\"desc\":\"This is just a test\",\"cost\":{},\"requires\":[],\"effects\":[{\"IncMultiplier\":0.2}]}"
     );
     assert_eq!(
         json2,
-        "{\"id\":\"test-upgrade\",\"name\":\"test upgrade\",\"desc\":\"This is just a test\",\"cost\":{\"cash\":0,\"xp\":0},\"requires\":[\"some-upgrade\",\"some-other-upgrade\"],\"effects\":[{\"IncMultiplier\":0.2},{\"AutoSolve\":[\"Easy\",\"Misc\"]}]}"
+        "{\"id\":\"test-upgrade\",\"name\":\"test upgrade\",\"desc\":\"This is just a test\",\"cost\":{},\"requires\":[\"some-upgrade\",\"some-other-upgrade\"],\"effects\":[{\"IncMultiplier\":0.2},{\"AutoSolve\":[\"Easy\",\"Misc\"]}]}"
     );
 }
 
+#[test]
+fn unit_effect_json() {
+    let effects = vec![
+        Effects::AutoClose,
+        Effects::AutoSolve(Difficulty::Easy, Category::Misc),
+        Effects::IncPriorityMultiplier(Priority::P1, 1.5),
+    ];
+    let json = serde_json::to_string(&effects).unwrap();
+    assert_eq!(
+        json,
+        "[\"AutoClose\",{\"AutoSolve\":[\"Easy\",\"Misc\"]},{\"IncPriorityMultiplier\":[\"P1\",1.5]}]"
+    );
+    let parsed: Vec<Effects> = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed, effects);
+}
+
 #[test]
 fn from_json() {
     let json1 = "{\"id\":\"test-upgrade\",\"name\":\"test upgrade\",\"desc\":\"This is just a test\",\"cost\":{\"cash\":0,\"xp\":0},\"requires\":[],\"effects\":[{\"IncMultiplier\":0.2}]}";
@@ -49,6 +69,8 @@ fn from_json() {
         cost: Currency::new(),
         requires: Vec::new(),
         effects: vec![Effects::IncMultiplier(0.2)],
+        repeat: None,
+        reputation: None,
     };
     let upgrade2 = Upgrade {
         id: "test-upgrade".to_string(),
@@ -60,11 +82,79 @@ fn from_json() {
             Effects::IncMultiplier(0.2),
             Effects::AutoSolve(Difficulty::Easy, Category::Misc),
         ],
+        repeat: None,
+        reputation: None,
     };
     assert_eq!(parsed1, upgrade1);
     assert_eq!(parsed2, upgrade2);
 }
 
+#[cfg(test)]
+fn price(cash: u64, xp: u64) -> Currency {
+    let mut cost = Currency::new();
+    cost.add_cash(cash);
+    cost.add_xp(xp);
+    cost
+}
+
+#[cfg(test)]
+fn repeatable(cost: CostCurve) -> Upgrade {
+    Upgrade {
+        id: "coffee".to_string(),
+        name: "Coffee".to_string(),
+        desc: String::new(),
+        cost: price(100, 10),
+        requires: Vec::new(),
+        effects: vec![Effects::IncMultiplier(1.1)],
+        repeat: Some(Repeat { max: None, cost }),
+        reputation: None,
+    }
+}
+
+#[test]
+fn repeat_json() {
+    let json = "{\"id\":\"coffee\",\"name\":\"Coffee\",\"desc\":\"\",\"cost\":{\"cash\":100,\"xp\":10},\"requires\":[],\"effects\":[{\"IncMultiplier\":1.1}],\"repeat\":{\"cost\":{\"Exponential\":1.5}}}";
+    let parsed: Upgrade = serde_json::from_str(json).unwrap();
+    assert_eq!(parsed, repeatable(CostCurve::Exponential(1.5)));
+}
+
+#[test]
+fn one_off_cost() {
+    let mut upgrade = repeatable(CostCurve::Exponential(2.0));
+    upgrade.repeat = None;
+    assert_eq!(upgrade.cost_at(0), price(100, 10));
+    assert_eq!(upgrade.max_level(), Some(1));
+}
+
+#[test]
+fn linear_cost() {
+    let upgrade = repeatable(CostCurve::Linear(price(50, 0)));
+    assert_eq!(upgrade.cost_at(0), price(100, 10));
+    assert_eq!(upgrade.cost_at(1), price(150, 10));
+    assert_eq!(upgrade.cost_at(4), price(300, 10));
+    assert_eq!(upgrade.max_level(), None);
+}
+
+#[test]
+fn exponential_cost() {
+    let upgrade = repeatable(CostCurve::Exponential(1.5));
+    assert_eq!(upgrade.cost_at(1), price(150, 15));
+    assert_eq!(upgrade.cost_at(2), price(225, 23));
+    // huge levels stop at the most a wallet can hold instead of overflowing
+    assert_eq!(upgrade.cost_at(1000).cash(), Amount::MAX);
+    assert_eq!(upgrade.cost_at(1000).xp(), Amount::MAX);
+}
+
+#[test]
+fn table_cost() {
+    let upgrade = repeatable(CostCurve::Table(vec![price(120, 0), price(500, 50)]));
+    assert_eq!(upgrade.cost_at(0), price(100, 10));
+    assert_eq!(upgrade.cost_at(1), price(120, 0));
+    assert_eq!(upgrade.cost_at(2), price(500, 50));
+    // the last price is used for every level after the table runs out
+    assert_eq!(upgrade.cost_at(9), price(500, 50));
+}
+
 /// Possible effects that improvements can have
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub enum Effects {
@@ -76,11 +166,37 @@ pub enum Effects {
     IncXPMultiplier(f32),
     /// which tickets can be autosolved
     AutoSolve(Difficulty, Category),
+    /// autosolved tickets close themselves once they're finished
+    AutoClose,
+    /// how much to increase the cash + XP from tickets of one priority
+    IncPriorityMultiplier(Priority, f32),
+}
+
+/// How the price of a repeatable upgrade goes up each time it's bought
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub enum CostCurve {
+    /// each level costs this much more than the one before
+    Linear(Currency),
+    /// each level costs this many times the one before
+    Exponential(f64),
+    /// prices of the levels after the first, the last one is used again for
+    /// any levels past the end of the table
+    Table(Vec<Currency>),
+}
+
+/// Lets an upgrade be bought more than once, its effects stack each time
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct Repeat {
+    /// Most times it can be bought, no limit if left out
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<u32>,
+    /// How the price goes up from `Upgrade::cost`
+    pub cost: CostCurve,
 }
 
 /// Information needed for buying/applying upgrades
-/// This is just data that should only be loaded from a file
-/// so it doesn't have a proper `impl` block
+/// This is mostly just data that should only be loaded from a file, the
+/// `impl` block only works out prices for repeatable upgrades
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub struct Upgrade {
     /// Unique ID for the upgrade (internal)
@@ -89,10 +205,56 @@ pub struct Upgrade {
     pub name: String,
     /// Upgrade description
     pub desc: String,
-    /// Cost in cash + xp
+    /// Cost in cash + xp (of the first level, for repeatable upgrades)
     pub cost: Currency,
     /// ID of upgrades that are needed before this becomes available
     pub requires: Vec<String>,
     /// What the upgrade actually does
     pub effects: Vec<Effects>,
+    /// Whether it can be bought again, upgrades without this are one-off
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repeat: Option<Repeat>,
+    /// Reputation needed before this becomes available, it isn't spent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reputation: Option<u64>,
+}
+
+impl Upgrade {
+    /// Price of the next level, when it's already been bought `level` times
+    pub fn cost_at(&self, level: u32) -> Currency {
+        let curve = match &self.repeat {
+            Some(repeat) if level > 0 => &repeat.cost,
+            _ => return self.cost.clone(),
+        };
+        match curve {
+            CostCurve::Linear(step) => {
+                let mut cost = self.cost.clone();
+                for (kind, n) in step.iter() {
+                    cost.add(kind, n * level as u64);
+                }
+                cost
+            }
+            CostCurve::Exponential(factor) => {
+                let scale = factor.powi(level as i32);
+                let mut cost = Currency::new();
+                for (kind, n) in self.cost.iter() {
+                    cost.add(kind, n.scale(scale));
+                }
+                cost
+            }
+            CostCurve::Table(prices) => prices
+                .get(level as usize - 1)
+                .or(prices.last())
+                .unwrap_or(&self.cost)
+                .clone(),
+        }
+    }
+
+    /// Most times it can be bought, None if there's no limit
+    pub fn max_level(&self) -> Option<u32> {
+        match &self.repeat {
+            Some(repeat) => repeat.max,
+            None => Some(1),
+        }
+    }
 }
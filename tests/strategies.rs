@@ -0,0 +1,64 @@
+//! Automated players run through the whole game to make sure progression
+//! never gets stuck
+
+use ticket_tycoon::game::GameState;
+use ticket_tycoon::strategy::*;
+
+/// A few clicks a second, about what a person keeps up while playing
+const CLICKS: u32 = 3;
+/// 4 hours of game time, which is far longer than any of them should need
+const SECS: i64 = 4 * 60 * 60;
+
+/// Plays until every upgrade in the catalog is bought, or until `SECS` runs out
+/// Returns how many seconds it took
+fn finish(seed: u64, strategy: &mut dyn Strategy) -> Option<i64> {
+    let mut game = GameState::with_seed(seed);
+    game.init_queue();
+    for secs in 1..=SECS {
+        play(&mut game, strategy, 1);
+        if game.purchased().len() == game.upgrades().len() {
+            return Some(secs);
+        }
+        // something should always be for sale until everything is bought
+        assert!(!game.avail_upgrades().is_empty(), "dead end at {}s", secs);
+    }
+    None
+}
+
+#[test]
+fn greedy_buys_everything() {
+    for seed in 0..5 {
+        assert!(
+            finish(seed, &mut Greedy::new(CLICKS)).is_some(),
+            "seed {}",
+            seed
+        );
+    }
+}
+
+#[test]
+fn roi_buys_everything() {
+    for seed in 0..5 {
+        assert!(
+            finish(seed, &mut Roi::new(CLICKS)).is_some(),
+            "seed {}",
+            seed
+        );
+    }
+}
+
+#[test]
+fn random_buys_everything() {
+    for seed in 0..5 {
+        let mut strategy = RandomClicker::new(CLICKS, seed);
+        assert!(finish(seed, &mut strategy).is_some(), "seed {}", seed);
+    }
+}
+
+#[test]
+fn same_seed_same_finish() {
+    assert_eq!(
+        finish(9, &mut Greedy::new(CLICKS)),
+        finish(9, &mut Greedy::new(CLICKS))
+    );
+}